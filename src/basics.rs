@@ -1,27 +1,78 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Hash, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
-pub struct Field(pub [u8; 4]);
+// a well of any width, as tall as its cells fit in the bits
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Field {
+  pub width: u8,
+  // row-major, the cell at column `x` of row `y` is the bit `y * width + x`
+  pub cells: u128
+}
 
-impl std::fmt::Display for Field {
-  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-    let mut result = [["  "; 4]; 8];
-    for i in 0..4 {
-      for (j, item) in result.iter_mut().enumerate() {
-        if self.0[i] & (1 << j) != 0 {
-          item[i] = "XX";
-        }
+impl Field {
+  pub const MAX_CELLS: usize = u128::BITS as usize;
+  pub fn empty(width: usize) -> Self {
+    assert!(width > 0 && width <= Self::MAX_CELLS, "invalid width {}", width);
+    Field { width: width as u8, cells: 0 }
+  }
+  pub fn width(&self) -> usize {
+    self.width as usize
+  }
+  // the number of rows the field can hold
+  pub fn max_height(&self) -> usize {
+    Self::MAX_CELLS / self.width()
+  }
+  pub fn get(&self, x: usize, y: usize) -> bool {
+    self.cells & (1 << (y * self.width() + x)) != 0
+  }
+  pub fn set(&mut self, x: usize, y: usize) {
+    assert!(x < self.width() && y < self.max_height(), "cell ({}, {}) out of the field", x, y);
+    self.cells |= 1 << (y * self.width() + x);
+  }
+  pub fn row(&self, y: usize) -> u128 {
+    (self.cells >> (y * self.width())) & self.full_row()
+  }
+  pub fn full_row(&self) -> u128 {
+    u128::MAX >> (Self::MAX_CELLS - self.width())
+  }
+  // the number of rows up to the highest filled cell
+  pub fn height(&self) -> usize {
+    (Self::MAX_CELLS - self.cells.leading_zeros() as usize).div_ceil(self.width())
+  }
+  pub fn count(&self) -> usize {
+    self.cells.count_ones() as usize
+  }
+  // flips the columns left to right
+  pub fn mirror(&self) -> Self {
+    let mut mirrored = Field::empty(self.width());
+    for y in 0..self.height() {
+      for x in (0..self.width()).filter(|&x| self.get(x, y)) {
+        mirrored.set(self.width() - 1 - x, y);
       }
     }
+    mirrored
+  }
+  // the one-line form parsed by `FromStr`
+  pub fn notation(&self) -> String {
+    (0..self.height().max(1))
+      .rev()
+      .map(|y| {
+        (0..self.width()).map(|x| if self.get(x, y) { 'X' } else { '.' }).collect::<String>()
+      })
+      .collect::<Vec<_>>()
+      .join("/")
+  }
+}
+
+impl std::fmt::Display for Field {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
     write!(
       f,
       "{}",
-      result
-        .iter()
-        .enumerate()
+      (0..self.height().max(1))
         .rev()
-        .map(|(i, line)| {
-          format!("{} |{}|", i, line.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(""))
+        .map(|y| {
+          let line = (0..self.width()).map(|x| if self.get(x, y) { "XX" } else { "  " });
+          format!("{} |{}|", y, line.collect::<Vec<_>>().join(""))
         })
         .collect::<Vec<_>>()
         .join("\n")
@@ -29,6 +80,34 @@ impl std::fmt::Display for Field {
   }
 }
 
+// rows from top to bottom, separated by newlines or `/`,
+// with `X` for filled cells and `.` for empty ones
+impl std::str::FromStr for Field {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let rows =
+      s.split(['\n', '/']).map(str::trim).filter(|row| !row.is_empty()).collect::<Vec<_>>();
+    let width = rows.first().ok_or_else(|| format!("no rows in {:?}", s))?.chars().count();
+    if width == 0 || width * rows.len() > Self::MAX_CELLS {
+      return Err(format!("{:?} does not fit in {} cells", s, Self::MAX_CELLS));
+    }
+    let mut field = Field::empty(width);
+    for (y, row) in rows.iter().rev().enumerate() {
+      if row.chars().count() != width {
+        return Err(format!("row {:?} is not {} cells wide", row, width));
+      }
+      for (x, c) in row.chars().enumerate() {
+        match c {
+          'X' | 'x' => field.set(x, y),
+          '.' => {}
+          _ => return Err(format!("invalid cell {:?} in row {:?}", c, row))
+        }
+      }
+    }
+    Ok(field)
+  }
+}
+
 #[derive(Debug, Hash, Eq, PartialEq, Serialize, Deserialize, Copy, Clone, Ord, PartialOrd)]
 pub enum Piece {
   I = 0,
@@ -53,6 +132,27 @@ impl Piece {
       _ => panic!("invalid piece number {}", num)
     }
   }
+  // the piece that looks the same in a mirrored field
+  pub fn mirror(&self) -> Piece {
+    match self {
+      Piece::S => Piece::Z,
+      Piece::Z => Piece::S,
+      Piece::J => Piece::L,
+      Piece::L => Piece::J,
+      piece => *piece
+    }
+  }
+}
+
+impl std::str::FromStr for Piece {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    PIECES
+      .iter()
+      .find(|piece| format!("{:?}", piece).eq_ignore_ascii_case(s.trim()))
+      .copied()
+      .ok_or_else(|| format!("invalid piece {:?}", s))
+  }
 }
 
 pub const PIECES: [Piece; 7] =
@@ -0,0 +1,141 @@
+use c4w::basics::{Field, Piece};
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+use crossterm::{queue, style, style::Stylize};
+use itertools::Itertools;
+use std::io::{stdin, stdout, BufRead, Write};
+
+/// Rank the moves of the positions typed in, from the policy file saved by extract_policy or c4w.
+///
+/// Each line is a field, the hold piece if the policy holds (`-` for the empty hold), the current
+/// piece followed by the preview and optionally the pieces left in the bag after the preview if
+/// the policy draws from bags, separated by spaces, like `...X/X..X/X..X/X..X O SZ IOTJL`.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the policy file
+  #[arg(long)]
+  policy: std::path::PathBuf
+}
+
+struct Position {
+  field: Field,
+  hold: Option<Piece>,
+  queue: Vec<Piece>,
+  left: Option<Vec<usize>>
+}
+
+fn parse_position(line: &str, hold: bool) -> Result<Position, String> {
+  let mut words = line.split_whitespace();
+  let field = words.next().ok_or("no field")?.parse::<Field>()?;
+  let hold = match hold.then(|| words.next().ok_or("no hold")).transpose()? {
+    None | Some("-") => None,
+    Some(word) => Some(word.parse::<Piece>()?)
+  };
+  let queue = words
+    .next()
+    .ok_or("no queue")?
+    .chars()
+    .map(|piece| piece.to_string().parse::<Piece>())
+    .collect::<Result<Vec<_>, _>>()?;
+  let left = words.next().map(BagSequenceStates::parse_bag).transpose()?;
+  if let Some(word) = words.next() {
+    return Err(format!("unexpected {:?}", word));
+  }
+  Ok(Position { field, hold, queue, left })
+}
+
+fn advise<S: SequenceStates>(
+  policy: &Policy<FieldSequenceStates<S>>,
+  probabilities: &[f64],
+  position: &Position
+) -> Result<(), String> {
+  let original = &policy.original;
+  if position.left.is_some() && original.sequence().bag().is_none() {
+    return Err("the policy has no bags".into());
+  }
+  let (&current, preview) = position.queue.split_first().ok_or("the queue is empty")?;
+  if preview.len() != original.sequence().preview() {
+    return Err(format!(
+      "the policy needs {} pieces in the preview",
+      original.sequence().preview()
+    ));
+  }
+  let found = original.find_branches(
+    &position.field,
+    original.hold_index(position.hold),
+    current,
+    preview,
+    position.left.as_deref(),
+    probabilities
+  );
+  if found.is_empty() {
+    return Err("the position is not in the policy".into());
+  }
+  let branches =
+    found.iter().map(|&(_, branch, probability)| (branch, probability)).collect_vec();
+  // a stable sort keeps the moves tied in the order of `next_moves`
+  let moves =
+    policy.average_moves(&branches).into_iter().sorted_by(|a, b| b.3.total_cmp(&a.3)).collect_vec();
+  let mut stdout = stdout();
+  println!("compatible states: {}, moves: {}", found.len(), moves.len());
+  for (rank, (next, piece, placement, value)) in moves.into_iter().enumerate() {
+    let (field, hold, _) = next;
+    let action = match placement {
+      Some(placement) if piece != current => {
+        format!("place {:?} at {}, hold {:?}", piece, placement, current)
+      }
+      Some(placement) => format!("place {:?} at {}", piece, placement),
+      None => format!("hold {:?}", current)
+    };
+    let line = format!("#{} {:.6} combos: {}", rank + 1, value, action);
+    let color = if rank == 0 { style::Color::Green } else { style::Color::Reset };
+    queue!(stdout, style::PrintStyledContent(line.with(color))).unwrap();
+    println!();
+    if original.hold() {
+      match original.held(hold) {
+        Some(piece) => println!("hold: {:?}", piece),
+        None => println!("hold: empty")
+      }
+    }
+    println!("{}", original.fields()[field]);
+  }
+  Ok(())
+}
+
+fn run<S: SequenceStates>(policy: Policy<FieldSequenceStates<S>>) {
+  let hold = policy.original.hold();
+  let probabilities = policy.original.sequence().stationary();
+  let mut stdout = stdout();
+  let prompt = if hold { "field hold queue [bag]> " } else { "field queue [bag]> " };
+  let mut lines = stdin().lock().lines();
+  loop {
+    queue!(stdout, style::PrintStyledContent(prompt.with(style::Color::Cyan))).unwrap();
+    stdout.flush().unwrap();
+    let Some(line) = lines.next() else { break };
+    let line = line.unwrap();
+    if line.trim().is_empty() {
+      continue;
+    }
+    let advised =
+      parse_position(&line, hold).and_then(|position| advise(&policy, &probabilities, &position));
+    if let Err(err) = advised {
+      queue!(stdout, style::PrintStyledContent(err.with(style::Color::Red))).unwrap();
+      println!();
+    }
+  }
+  println!();
+}
+
+fn main() {
+  let args = Args::parse();
+  let policy: PolicyFile =
+    bincode::deserialize_from(std::fs::File::open(args.policy).unwrap()).unwrap();
+  match policy {
+    PolicyFile::Random(policy) => run(policy),
+    PolicyFile::Bag(policy) => run(policy),
+    PolicyFile::History(policy) => run(policy),
+    PolicyFile::Automaton(policy) => run(policy)
+  }
+}
@@ -0,0 +1,209 @@
+use c4w::basics::PIECES;
+use c4w::continuations;
+use c4w::evaluator::*;
+use c4w::minimizer::*;
+use c4w::printer::*;
+use c4w::states::*;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Sequence {
+  /// Every piece equally likely
+  Random,
+  /// Pieces drawn from bags, see --bag
+  Bag,
+  /// Pieces rerolled against the history, see --history and --rolls
+  History,
+  /// Pieces from an automaton file, see --automaton
+  Automaton
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MinimizerKind {
+  Parallel,
+  Dashmap,
+  Recorder,
+  Conservative,
+  Raw
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PrinterKind {
+  /// The value averaged over every state
+  Average,
+  /// The value of every state
+  Raw,
+  /// The value of every field averaged over the holds and the sequences, best first
+  Field,
+  /// The value of every field with every hold averaged over the sequences, best first
+  FieldHold
+}
+
+/// Calculate the best 4w policy and print its expected combos, or the expected lines sent with
+/// --attack-table.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the continuation file, in either the bincode or the text format
+  #[arg(long)]
+  continuation: std::path::PathBuf,
+
+  /// Number of previews
+  #[arg(long)]
+  preview: usize,
+
+  /// Whether hold is enabled
+  #[arg(long, action)]
+  hold: bool,
+
+  /// Add the states with the empty hold, where holding into it pulls the next piece, which
+  /// then has to be placed without holding (pull)
+  #[arg(long, requires = "hold")]
+  first_hold: Option<FirstHold>,
+
+  /// The sequence model
+  #[arg(long, value_enum, default_value_t = Sequence::Bag)]
+  sequence: Sequence,
+
+  /// The pieces of each bag for the bag sequence
+  #[arg(long, default_value = "IOTSZJL", value_parser = BagSequenceStates::parse_bag)]
+  bag: std::vec::Vec<usize>,
+
+  /// The number of the last pieces the history sequence rerolls against
+  #[arg(long, default_value_t = 4)]
+  history: usize,
+
+  /// The number of rolls of the history sequence
+  #[arg(long, default_value_t = 4)]
+  rolls: usize,
+
+  /// The automaton file of the automaton sequence
+  #[arg(
+    long,
+    required_if_eq("sequence", "automaton"),
+    value_parser = |path: &str| Automaton::load(path.as_ref())
+  )]
+  automaton: Option<Automaton>,
+
+  /// Whether to merge every state with its mirror image (J and L, S and Z swapped) first
+  #[arg(long, action)]
+  mirror: bool,
+
+  /// The minimizer
+  #[arg(long, value_enum, default_value_t = MinimizerKind::Parallel)]
+  minimizer: MinimizerKind,
+
+  /// Maximize the lines sent by the attack table instead of the combos, `guideline` or the path
+  /// to a table file. The raw states are solved for every combo index of the table without
+  /// minimizing, taking 8 bytes per state and combo index
+  #[arg(long, value_parser = parse_attack_table, conflicts_with_all = ["mirror", "first_hold"])]
+  attack_table: Option<AttackTable>,
+
+  /// The path to save the policy file to, to be used by query_policy, advisor, simulate and
+  /// c4w_tbp as the one saved by extract_policy
+  #[arg(long, conflicts_with = "attack_table")]
+  policy: Option<std::path::PathBuf>,
+
+  /// The value iteration stops when no value changes more than this
+  #[arg(long, default_value_t = 1e-10)]
+  epsilon: f64,
+
+  /// What to print
+  #[arg(long, value_enum, default_value_t = PrinterKind::Average)]
+  printer: PrinterKind
+}
+
+fn parse_attack_table(table: &str) -> Result<AttackTable, String> {
+  match table {
+    "guideline" => Ok(AttackTable::guideline()),
+    path => AttackTable::load(path.as_ref())
+  }
+}
+
+fn print<S: SequenceStates>(
+  printer: PrinterKind,
+  mapping: &[usize],
+  values: &[f64],
+  original: &FieldSequenceStates<S>
+) {
+  match printer {
+    PrinterKind::Average => AveragePrinter::print(mapping, values, original),
+    PrinterKind::Raw => RawPrinter::print(mapping, values, original),
+    PrinterKind::Field => FieldPrinter::print(mapping, values, original),
+    PrinterKind::FieldHold => FieldHoldPrinter::print(mapping, values, original)
+  }
+}
+
+fn minimize<M: Minimizer, S: SequenceStates>(
+  states: FieldSequenceStates<S>,
+  mirror: bool
+) -> ConcreteMappedStates<FieldSequenceStates<S>> {
+  if mirror {
+    let mirrored = states.mirror().expect("the sequence is checked by `run`");
+    M::minimize(mirrored).concrete().compose()
+  } else {
+    M::minimize(states).concrete()
+  }
+}
+
+fn run<S: SequenceStates>(args: &Args, sequence: S)
+where
+  PolicyFile: From<Policy<FieldSequenceStates<S>>>
+{
+  let mirror = PIECES.map(|piece| piece.mirror() as usize);
+  if args.mirror && sequence.permute(&mirror).is_none() {
+    Args::command()
+      .error(ErrorKind::ArgumentConflict, "the sequence is not symmetric under mirroring")
+      .exit();
+  }
+  let continuations = continuations::load(&args.continuation);
+  let mut states = FieldSequenceStates::with_sequence(&continuations, args.hold, sequence);
+  if let Some(first_hold) = args.first_hold {
+    states = states.with_empty_hold(first_hold);
+  }
+  eprintln!("states: {}", states.len());
+  if let Some(table) = &args.attack_table {
+    // the minimized states forget the placements, and the values start the combo at index 0
+    let mut evaluator = RewardIterator::new(&states, table);
+    while evaluator.next().1 >= args.epsilon {}
+    let mapping = (0..states.len()).collect::<Vec<_>>();
+    print(args.printer, &mapping, &evaluator.values[..states.len()], &states);
+    return;
+  }
+  let minimized = match args.minimizer {
+    MinimizerKind::Parallel => minimize::<ParallelMinimizer, _>(states, args.mirror),
+    MinimizerKind::Dashmap => minimize::<DashMapMinimizer, _>(states, args.mirror),
+    MinimizerKind::Recorder => minimize::<RecorderMinimizer, _>(states, args.mirror),
+    MinimizerKind::Conservative => minimize::<ConservMinimizer, _>(states, args.mirror),
+    MinimizerKind::Raw => minimize::<RawMinimizer, _>(states, args.mirror)
+  };
+  eprintln!("minimized: {}", minimized.len());
+  let mut evaluator = ValueIterator::new(&minimized);
+  while evaluator.next().1 >= args.epsilon {}
+  print(args.printer, &minimized.mapping, &evaluator.values, &minimized.original);
+  if let Some(path) = &args.policy {
+    let values = evaluator.values;
+    let policy = PolicyFile::from(Policy::new(minimized, values));
+    bincode::serialize_into(std::fs::File::create(path).unwrap(), &policy).unwrap();
+  }
+}
+
+fn main() {
+  let args = Args::parse();
+  let base_len = PIECES.len();
+  match args.sequence {
+    Sequence::Random => run(&args, RandomSequenceStates::new(args.preview, base_len)),
+    Sequence::Bag => {
+      run(&args, BagSequenceStates::with_bag(args.preview, &args.bag))
+    }
+    Sequence::History => run(
+      &args,
+      HistorySequenceStates::with_history(args.preview, base_len, args.history, args.rolls)
+    ),
+    Sequence::Automaton => {
+      let automaton = args.automaton.as_ref().unwrap();
+      run(&args, AutomatonSequenceStates::with_automaton(args.preview, automaton))
+    }
+  }
+}
@@ -0,0 +1,413 @@
+use c4w::basics::{Field, Piece, PIECES};
+use c4w::continuations::{Placement, Spin};
+use c4w::evaluator::*;
+use c4w::rotation::RotationSystem;
+use c4w::states::*;
+use clap::Parser;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{stdin, BufRead};
+
+/// Suggest the moves of the policy file saved by extract_policy or c4w over the Tetris Bot
+/// Protocol, reading the messages of the frontend from stdin and writing the messages of the bot
+/// to stdout.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the policy file
+  #[arg(long)]
+  policy: std::path::PathBuf,
+
+  /// The path to the rotation system description the continuations are built with, SRS if not given
+  #[arg(long, value_parser = |path: &str| RotationSystem::load(path.as_ref()))]
+  rotation_system: Option<RotationSystem>,
+
+  /// The leftmost column of the well on the board
+  #[arg(long, default_value = "3")]
+  well: usize
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Orientation {
+  North,
+  East,
+  South,
+  West
+}
+
+const ORIENTATIONS: [Orientation; 4] =
+  [Orientation::North, Orientation::East, Orientation::South, Orientation::West];
+
+// the center of the piece on the board, with y upwards from the bottom row
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+  #[serde(rename = "type")]
+  piece: Piece,
+  orientation: Orientation,
+  x: i32,
+  y: i32
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TbpSpin {
+  None,
+  Mini,
+  Full
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct Move {
+  location: Location,
+  spin: TbpSpin
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FrontendMessage {
+  Rules {},
+  Start {
+    hold: Option<Piece>,
+    queue: Vec<Piece>,
+    board: Vec<Vec<Option<String>>>,
+    randomizer: Option<Randomizer>
+  },
+  Suggest {},
+  Play {
+    #[serde(rename = "move")]
+    mv: Move
+  },
+  NewPiece {
+    piece: Piece
+  },
+  Stop {},
+  Quit {},
+  #[serde(other)]
+  Unknown
+}
+
+// the pieces left in the bag after the queue, from the randomizer extension
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Randomizer {
+  SevenBag {
+    bag_state: Vec<Piece>
+  },
+  #[serde(other)]
+  Unknown
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BotMessage {
+  Info { name: &'static str, version: &'static str, author: &'static str, features: Vec<String> },
+  Ready {},
+  Suggestion { moves: Vec<Move> }
+}
+
+fn send(message: &BotMessage) {
+  println!("{}", serde_json::to_string(message).unwrap());
+}
+
+type Branch<S> = <FieldSequenceStates<S> as States>::Branch;
+
+// the minos of each piece facing north around its center, as in the protocol
+const MINOS: [[(i32, i32); 4]; 7] = [
+  [(-1, 0), (0, 0), (1, 0), (2, 0)],
+  [(0, 0), (1, 0), (0, 1), (1, 1)],
+  [(-1, 0), (0, 0), (1, 0), (0, 1)],
+  [(-1, 0), (0, 0), (0, 1), (1, 1)],
+  [(-1, 1), (0, 1), (0, 0), (1, 0)],
+  [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+  [(-1, 0), (0, 0), (1, 0), (1, 1)]
+];
+
+// the center of each piece in each orientation from the bottom-left corner of its bounding box
+fn centers(rotation_system: &RotationSystem) -> Vec<[(i32, i32); 4]> {
+  PIECES
+    .iter()
+    .map(|piece| {
+      let mut minos = MINOS[*piece as usize].to_vec();
+      std::array::from_fn(|orientation| {
+        let cells =
+          rotation_system.piece(piece).cells(orientation).into_iter().sorted().collect_vec();
+        let center = minos
+          .iter()
+          .map(|&(x, y)| (cells[0].0 - x, cells[0].1 - y))
+          .find(|&(cx, cy)| {
+            minos.iter().map(|&(x, y)| (cx + x, cy + y)).sorted().eq(cells.iter().cloned())
+          })
+          .unwrap_or_else(|| {
+            panic!(
+              "{:?} facing {:?} is not a piece of the protocol",
+              piece, ORIENTATIONS[orientation]
+            )
+          });
+        // clockwise
+        minos = minos.iter().map(|&(x, y)| (y, -x)).collect();
+        center
+      })
+    })
+    .collect()
+}
+
+struct Game {
+  field: usize,
+  hold: Option<Piece>,
+  queue: VecDeque<Piece>,
+  // the number of each piece left in the bag after the queue, if it is known,
+  // where an emptied bag is the same as a full one
+  bag: Option<Vec<usize>>
+}
+
+// the number of each piece left in the bag before `pieces` are drawn from it, given the number
+// left after them, `None` if they cannot be drawn
+fn put_back(mut bag: Vec<usize>, full: &[usize], pieces: &[Piece]) -> Option<Vec<usize>> {
+  for &piece in pieces.iter().rev() {
+    // the pieces before a full bag are drawn from the previous one
+    if bag == full {
+      bag.fill(0);
+    }
+    bag[piece as usize] += 1;
+    if bag[piece as usize] > full[piece as usize] {
+      return None;
+    }
+  }
+  Some(bag)
+}
+
+struct Bot<S: SequenceStates> {
+  policy: Policy<FieldSequenceStates<S>>,
+  // the probability of each sequence state in the long run
+  probabilities: Vec<f64>,
+  centers: Vec<[(i32, i32); 4]>,
+  well: usize,
+  // `None` out of the continuations
+  game: Option<Game>
+}
+
+impl<S: SequenceStates> Bot<S> {
+  fn location(&self, piece: Piece, placement: &Placement) -> Location {
+    let orientation = placement.orientation as usize;
+    let (x, y) = self.centers[piece as usize][orientation];
+    Location {
+      piece,
+      orientation: ORIENTATIONS[orientation],
+      x: self.well as i32 + placement.x as i32 + x,
+      y: placement.y as i32 + y
+    }
+  }
+  // the field in the well, if the board is filled outside the well up to the top of the stack
+  // and empty above it
+  fn read_board(&self, board: &[Vec<Option<String>>]) -> Option<usize> {
+    let fields = self.policy.original.fields();
+    let well = self.well..self.well + fields[0].width();
+    let stack = board
+      .iter()
+      .take_while(|row| {
+        row.iter().enumerate().any(|(x, cell)| !well.contains(&x) && cell.is_some())
+      })
+      .count();
+    let mut field = Field::empty(fields[0].width());
+    for (y, row) in board.iter().enumerate() {
+      for (x, cell) in row.iter().enumerate() {
+        if !well.contains(&x) {
+          if cell.is_some() != (y < stack) {
+            return None;
+          }
+        } else if cell.is_some() {
+          if y >= stack || y >= field.max_height() {
+            return None;
+          }
+          field.set(x - well.start, y);
+        }
+      }
+    }
+    fields.iter().position(|f| *f == field)
+  }
+  // the branches placing the first piece of the queue with the hold, one for each compatible
+  // sequence with its probability, where the pieces of the preview not in the queue are unknown
+  fn branches(&self, game: &Game, hold: usize, queue: &[Piece]) -> Vec<(Branch<S>, f64)> {
+    let original = &self.policy.original;
+    let Some((&current, rest)) = queue.split_first() else { return vec![] };
+    let preview = original.sequence().preview().min(rest.len());
+    // the pieces after the preview are put back into the bag
+    let full = original.sequence().bag();
+    let bag = game.bag.clone().zip(full).filter(|_| preview < rest.len());
+    let left = bag.and_then(|(bag, full)| put_back(bag, full, &rest[preview..]));
+    original
+      .find_branches(
+        &original.fields()[game.field],
+        hold,
+        current,
+        rest,
+        left.as_deref(),
+        &self.probabilities
+      )
+      .into_iter()
+      .map(|(_, branch, probability)| (branch, probability))
+      .collect()
+  }
+  // the moves ranked by the expected combos after them, none if the policy cannot tell
+  fn suggest(&self) -> Vec<Move> {
+    let Some(game) = &self.game else { return vec![] };
+    let original = &self.policy.original;
+    if original.hold() && game.hold.is_none() && original.empty_hold().is_none() {
+      eprintln!("the policy has no empty hold");
+      return vec![];
+    }
+    let queue = game.queue.iter().cloned().collect_vec();
+    let branches = self.branches(game, original.hold_index(game.hold), &queue);
+    let mut moves = vec![];
+    for ((_, hold, _), piece, placement, value) in self.policy.average_moves(&branches) {
+      match placement {
+        Some(placement) => moves.push((piece, placement, value)),
+        // holding into the empty hold brings out the next piece, which is placed in the same move
+        None => {
+          let branches = self.branches(game, hold, &queue[1..]);
+          moves.extend(self.policy.average_moves(&branches).into_iter().filter_map(
+            |(_, next, placement, value)| {
+              Some((next, placement?, value)).filter(|_| next == queue[1])
+            }
+          ));
+        }
+      }
+    }
+    moves
+      .into_iter()
+      .sorted_by(|a, b| b.2.total_cmp(&a.2))
+      .map(|(piece, placement, _)| Move {
+        location: self.location(piece, &placement),
+        // the protocol only has T-spins
+        spin: match placement.spin {
+          Spin::TSpin => TbpSpin::Full,
+          Spin::TSpinMini => TbpSpin::Mini,
+          Spin::None | Spin::AllSpin => TbpSpin::None
+        }
+      })
+      .collect()
+  }
+  // the game after the move, `None` if it leaves the continuations
+  fn play(&self, game: Game, mv: &Move) -> Option<Game> {
+    let Game { field, mut hold, mut queue, bag } = game;
+    let piece = mv.location.piece;
+    // as in the protocol, the hold is used if the piece is not the current one
+    let current = queue.pop_front()?;
+    if current != piece {
+      if !self.policy.original.hold() {
+        return None;
+      }
+      let held = hold.replace(current).or_else(|| queue.pop_front());
+      if held != Some(piece) {
+        return None;
+      }
+    }
+    let &(field, _) = self
+      .policy
+      .original
+      .field_moves(field, piece)
+      .iter()
+      .find(|(_, placement)| self.location(piece, placement) == mv.location)?;
+    Some(Game { field, hold, queue, bag })
+  }
+}
+
+fn run<S: SequenceStates>(args: Args, policy: Policy<FieldSequenceStates<S>>) {
+  let rotation_system = args.rotation_system.unwrap_or_else(RotationSystem::srs);
+  let probabilities = policy.original.sequence().stationary();
+  let mut bot = Bot {
+    policy,
+    probabilities,
+    centers: centers(&rotation_system),
+    well: args.well,
+    game: None
+  };
+
+  send(&BotMessage::Info {
+    name: "c4w",
+    version: env!("CARGO_PKG_VERSION"),
+    author: "c4w",
+    features: vec![]
+  });
+  for line in stdin().lock().lines() {
+    let line = line.unwrap();
+    let message = match serde_json::from_str::<FrontendMessage>(&line) {
+      Ok(message) => message,
+      Err(err) => {
+        eprintln!("invalid message {:?}: {}", line, err);
+        continue;
+      }
+    };
+    match message {
+      FrontendMessage::Rules {} => send(&BotMessage::Ready {}),
+      FrontendMessage::Start { hold, queue, board, randomizer } => {
+        let full = bot.policy.original.sequence().bag();
+        let bag = match (randomizer, full) {
+          (Some(Randomizer::SevenBag { bag_state }), Some(full))
+            if full.iter().all(|&count| count == 1) =>
+          {
+            let mut bag = vec![0; full.len()];
+            bag_state.iter().for_each(|&piece| bag[piece as usize] += 1);
+            let valid = bag.iter().all(|&count| count <= 1)
+              && put_back(bag.clone(), full, &queue).is_some();
+            if !valid {
+              eprintln!("the bag state does not match the queue");
+            }
+            Some(bag).filter(|_| valid)
+          }
+          _ => None
+        };
+        bot.game =
+          bot.read_board(&board).map(|field| Game { field, hold, queue: queue.into(), bag });
+        if bot.game.is_none() {
+          eprintln!("the board is not a field of the continuations");
+        }
+      }
+      FrontendMessage::Suggest {} => send(&BotMessage::Suggestion { moves: bot.suggest() }),
+      FrontendMessage::Play { mv } => {
+        bot.game = bot.game.take().and_then(|game| {
+          let game = bot.play(game, &mv);
+          if game.is_none() {
+            eprintln!("{:?} leaves the continuations", mv.location);
+          }
+          game
+        });
+      }
+      FrontendMessage::NewPiece { piece } => {
+        let full = bot.policy.original.sequence().bag();
+        if let Some(game) = &mut bot.game {
+          game.queue.push_back(piece);
+          if let Some((mut bag, full)) = game.bag.take().zip(full) {
+            if bag.iter().all(|&count| count == 0) {
+              bag = full.to_vec();
+            }
+            match bag[piece as usize].checked_sub(1) {
+              Some(count) => {
+                bag[piece as usize] = count;
+                game.bag = Some(bag);
+              }
+              None => eprintln!("{:?} is not left in the bag", piece)
+            }
+          }
+        }
+      }
+      FrontendMessage::Stop {} => bot.game = None,
+      FrontendMessage::Quit {} => break,
+      FrontendMessage::Unknown => eprintln!("unknown message {:?}", line)
+    }
+  }
+}
+
+fn main() {
+  let args = Args::parse();
+  let policy: PolicyFile =
+    bincode::deserialize_from(std::fs::File::open(&args.policy).unwrap()).unwrap();
+  match policy {
+    PolicyFile::Random(policy) => run(args, policy),
+    PolicyFile::Bag(policy) => run(args, policy),
+    PolicyFile::History(policy) => run(args, policy),
+    PolicyFile::Automaton(policy) => run(args, policy)
+  }
+}
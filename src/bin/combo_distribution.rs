@@ -0,0 +1,67 @@
+use c4w::basics::Field;
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+
+/// Print the distribution of the combo count under the policy maximizing the expected combos.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the state file saved by proved_minimizer
+  #[arg(long)]
+  state_file: std::path::PathBuf,
+
+  /// The largest combo count to compute the probability of
+  #[arg(long, default_value_t = 20)]
+  max_combo: usize,
+
+  /// A starting field to print, like `...X/X..X/X..X/X..X`, every field if not given
+  #[arg(long)]
+  field: Vec<Field>
+}
+
+const EPS: f64 = 1e-10;
+
+fn main() {
+  let args = Args::parse();
+  let minimized: ConcreteMappedStates<FieldSequenceStates<BagSequenceStates>> =
+    bincode::deserialize_from(std::fs::File::open(args.state_file).unwrap()).unwrap();
+  let mut evaluator = ValueIterator::new(&minimized);
+  while evaluator.next().1 >= EPS {}
+  let values = evaluator.values;
+  let mut distribution = ComboDistribution::new(&minimized, &values);
+  // one more for the extra moves of the empty hold
+  for _ in 0..=args.max_combo {
+    distribution.next();
+  }
+
+  // every starting state of a field is equally likely
+  let original = &minimized.original;
+  let fields = original.fields();
+  let mut sums = vec![(0, 0., vec![0.; args.max_combo]); fields.len()];
+  for i in 0..original.len() {
+    let state = original.decode(i).unwrap();
+    let extra = original.extra_moves(&state);
+    let class = minimized.mapping[i];
+    let (count, value, tail) = &mut sums[state.0];
+    *count += 1;
+    *value += values[class] - extra as f64;
+    // the combos reach a count when the moves reach the count plus the extra moves
+    let probabilities = distribution.tail(class).into_iter().skip(extra);
+    for (sum, probability) in tail.iter_mut().zip(probabilities) {
+      *sum += probability;
+    }
+  }
+  for (field, (count, value, tail)) in fields.iter().zip(sums) {
+    if !args.field.is_empty() && !args.field.contains(field) {
+      continue;
+    }
+    println!("{}", field);
+    println!("expected combos: {:.6}", value / count as f64);
+    println!("combos P(<= combos)");
+    for (combo, probability) in tail.iter().enumerate() {
+      println!("{} {:.6}", combo, 1. - probability / count as f64);
+    }
+    println!();
+  }
+}
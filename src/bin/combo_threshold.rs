@@ -0,0 +1,85 @@
+use c4w::basics::Field;
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+use rayon::prelude::*;
+
+/// Compare the best probability of reaching a combo count with the policy maximizing the expected combos.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the state file saved by proved_minimizer
+  #[arg(long)]
+  state_file: std::path::PathBuf,
+
+  /// The combo count to reach
+  #[arg(long)]
+  target: usize,
+
+  /// A starting field to print, like `...X/X..X/X..X/X..X`, every field if not given
+  #[arg(long)]
+  field: Vec<Field>
+}
+
+const EPS: f64 = 1e-10;
+
+fn main() {
+  let args = Args::parse();
+  assert!(args.target > 0, "the target must be at least 1 combo");
+  let minimized: ConcreteMappedStates<FieldSequenceStates<BagSequenceStates>> =
+    bincode::deserialize_from(std::fs::File::open(args.state_file).unwrap()).unwrap();
+  let mut evaluator = ValueIterator::new(&minimized);
+  while evaluator.next().1 >= EPS {}
+  let values = evaluator.values;
+  let mut distribution = ComboDistribution::new(&minimized, &values);
+  let mut threshold = ThresholdIterator::new(&minimized);
+  // one more for the extra moves of the empty hold
+  for _ in 0..=args.target {
+    distribution.next();
+    threshold.next();
+  }
+
+  // the first choices where following the expected combos loses some chance of the target
+  let (decisions, different) = (0..minimized.len())
+    .into_par_iter()
+    .flat_map_iter(|state| minimized.next_pieces(state))
+    .filter_map(|branch| {
+      let best = threshold.choose(branch, args.target)?;
+      let expected = distribution.choose(branch).unwrap();
+      let layer = &threshold.layers[args.target - 1];
+      Some((1, (layer[expected] < layer[best]) as usize))
+    })
+    .reduce(|| (0, 0), |(a, b), (c, d)| (a + c, b + d));
+  println!(
+    "the expected-value policy is worse for {} combos at {} of {} first choices",
+    args.target, different, decisions
+  );
+  println!();
+
+  // every starting state of a field is equally likely
+  let original = &minimized.original;
+  let fields = original.fields();
+  let mut sums = vec![(0, 0., 0.); fields.len()];
+  for i in 0..original.len() {
+    let state = original.decode(i).unwrap();
+    let target = args.target + original.extra_moves(&state);
+    let class = minimized.mapping[i];
+    let (count, best, expected) = &mut sums[state.0];
+    *count += 1;
+    *best += threshold.layers[target][class];
+    *expected += distribution.tails[target - 1][class];
+  }
+  for (field, (count, best, expected)) in fields.iter().zip(sums) {
+    if !args.field.is_empty() && !args.field.contains(field) {
+      continue;
+    }
+    println!("{}", field);
+    println!("P(>= {} combos) with the threshold policy: {:.6}", args.target, best / count as f64);
+    println!(
+      "P(>= {} combos) with the expected-value policy: {:.6}",
+      args.target,
+      expected / count as f64
+    );
+    println!();
+  }
+}
@@ -0,0 +1,39 @@
+use c4w::continuations::{self, Continuations};
+use clap::{Parser, ValueEnum};
+use std::io::Write;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+  Bincode,
+  Text
+}
+
+/// Convert a continuation file between the bincode and the text format.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the continuation file, in either format
+  #[arg(long)]
+  input: std::path::PathBuf,
+
+  /// The format to convert to
+  #[arg(long, value_enum)]
+  to: Format,
+
+  /// The path to save the converted file, stdout if not given
+  #[arg(long)]
+  output: Option<std::path::PathBuf>
+}
+
+fn main() {
+  let args = Args::parse();
+  let continuations: Continuations = continuations::load(&args.input);
+  let mut output: Box<dyn Write> = match args.output {
+    Some(path) => Box::new(std::fs::File::create(path).unwrap()),
+    None => Box::new(std::io::stdout())
+  };
+  match args.to {
+    Format::Bincode => bincode::serialize_into(&mut output, &continuations).unwrap(),
+    Format::Text => output.write_all(continuations::to_text(&continuations).as_bytes()).unwrap()
+  }
+}
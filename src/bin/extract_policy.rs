@@ -0,0 +1,39 @@
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+
+/// Solve the states and save the best move of every state, to be queried by query_policy.
+/// Only the bag sequence is supported, as saved by proved_minimizer. c4w --policy saves the
+/// policy of any sequence.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the state file saved by proved_minimizer, with the bag sequence
+  #[arg(long)]
+  state_file: std::path::PathBuf,
+
+  /// The value iteration stops when no value changes by this much
+  #[arg(long, default_value = "1e-10")]
+  epsilon: f64,
+
+  /// The path to save the policy file
+  #[arg(long)]
+  output: std::path::PathBuf
+}
+
+fn main() {
+  let args = Args::parse();
+  let minimized: ConcreteMappedStates<FieldSequenceStates<BagSequenceStates>> =
+    bincode::deserialize_from(std::fs::File::open(args.state_file).unwrap()).unwrap();
+  let mut evaluator = ValueIterator::new(&minimized);
+  loop {
+    let (_, diff) = evaluator.next();
+    eprintln!("diff: {}", diff);
+    if diff < args.epsilon {
+      break;
+    }
+  }
+  let values = evaluator.values;
+  let policy = PolicyFile::from(Policy::new(minimized, values));
+  bincode::serialize_into(std::fs::File::create(args.output).unwrap(), &policy).unwrap();
+}
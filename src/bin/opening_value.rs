@@ -0,0 +1,116 @@
+use c4w::basics::{Field, Piece, PIECES};
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug)]
+enum Hold {
+  Empty,
+  // every piece equally likely
+  Any,
+  Piece(Piece)
+}
+
+impl std::str::FromStr for Hold {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "empty" => Ok(Hold::Empty),
+      "any" => Ok(Hold::Any),
+      _ => s.parse().map(Hold::Piece)
+    }
+  }
+}
+
+/// Print the expected combos at the start of a game, averaged over the compatible states.
+/// Only the bag sequence is supported, as saved by proved_minimizer.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the state file saved by proved_minimizer, with the bag sequence
+  #[arg(long)]
+  state_file: std::path::PathBuf,
+
+  /// The starting field, like `...X/X..X/X..X/X..X`
+  #[arg(long)]
+  field: Field,
+
+  /// The hold at the start: `empty` (needs a state file with --first-hold), `any` for every
+  /// piece equally likely, or a piece. Empty if the state file has the empty hold, otherwise any
+  #[arg(long)]
+  hold: Option<Hold>,
+
+  /// The number of pieces of the first bag drawn before the queue, 0 for a fresh bag.
+  /// Every given position is equally likely
+  #[arg(long, default_value = "0")]
+  bag_position: Vec<usize>,
+
+  /// The first pieces of the queue, like `IOT`, if they are revealed
+  #[arg(long, default_value = "")]
+  queue: String
+}
+
+const EPS: f64 = 1e-10;
+
+fn error(kind: ErrorKind, message: impl std::fmt::Display) -> ! {
+  Args::command().error(kind, message).exit()
+}
+
+fn main() {
+  let args = Args::parse();
+  let minimized: ConcreteMappedStates<FieldSequenceStates<BagSequenceStates>> =
+    bincode::deserialize_from(std::fs::File::open(args.state_file).unwrap()).unwrap();
+  let original = &minimized.original;
+  let field = original
+    .fields()
+    .iter()
+    .position(|field| *field == args.field)
+    .unwrap_or_else(|| {
+      let message = format!("{} is not in the state file", args.field.notation());
+      error(ErrorKind::ValueValidation, message)
+    });
+  let holds: Vec<usize> = match args.hold {
+    None if !original.hold() => vec![0],
+    Some(_) if !original.hold() => error(ErrorKind::ArgumentConflict, "the state file has no hold"),
+    None if original.empty_hold().is_some() => vec![original.empty_hold().unwrap()],
+    Some(Hold::Empty) => vec![original.empty_hold().unwrap_or_else(|| {
+      error(ErrorKind::ArgumentConflict, "the state file has no empty hold")
+    })],
+    None | Some(Hold::Any) => (0..PIECES.len()).collect(),
+    Some(Hold::Piece(piece)) => vec![piece as usize]
+  };
+  let queue = args
+    .queue
+    .chars()
+    .map(|piece| piece.to_string().parse::<Piece>().map(|piece| piece as usize))
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap_or_else(|err| error(ErrorKind::ValueValidation, format!("--queue: {}", err)));
+
+  let mut evaluator = ValueIterator::new(&minimized);
+  while evaluator.next().1 >= EPS {}
+  let values = evaluator.values;
+
+  // the same state may start a game from several bag positions
+  let mut compatible = HashSet::new();
+  let mut value = 0.;
+  for &drawn in args.bag_position.iter() {
+    let starts = original
+      .sequence()
+      .starts(drawn, &queue)
+      .unwrap_or_else(|err| error(ErrorKind::ValueValidation, format!("--queue: {}", err)));
+    for (sequence, probability) in starts {
+      for &hold in holds.iter() {
+        let state = (field, hold, sequence);
+        let index = original.encode(&state).unwrap();
+        let state_value = values[minimized.mapping[index]] - original.extra_moves(&state) as f64;
+        compatible.insert(index);
+        value += probability * state_value / (args.bag_position.len() * holds.len()) as f64;
+      }
+    }
+  }
+  println!("{}", args.field);
+  println!("compatible states: {}", compatible.len());
+  println!("expected combos: {:.6}", value);
+}
@@ -1,12 +1,11 @@
-use c4w::basics::*;
-use c4w::evaluator::*;
+use c4w::continuations;
 use c4w::minimizer::*;
 
 use c4w::prover::*;
 use c4w::pruner::*;
 use c4w::states::*;
-use std::collections::HashMap;
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use bit_vec::BitVec;
 
 fn report<T: States>(minimized: &ConcreteMappedStates<T>) {
@@ -37,10 +36,24 @@ struct Args {
     #[arg(long, action)]
     hold: bool,
 
-    /// the path to the continuation file
+    /// Add the states with the empty hold, where holding into it pulls the next piece, which
+    /// then has to be placed without holding (pull)
+    #[arg(long, requires = "hold")]
+    first_hold: Option<FirstHold>,
+
+    /// the path to the continuation file, in either the bincode or the text format
     #[arg(long)]
     continuation: std::path::PathBuf,
 
+    /// Whether to merge every state with its mirror image (J and L, S and Z swapped) first.
+    /// Fields whose continuations are not mirrored, e.g. due to asymmetric I kicks, stay apart
+    #[arg(long, action)]
+    mirror: bool,
+
+    /// The pieces of each bag, like `IOTSZJLIOTSZJL` for the 14-bag. One of each piece if not given
+    #[arg(long, default_value = "IOTSZJL", value_parser = BagSequenceStates::parse_bag)]
+    bag: std::vec::Vec<usize>,
+
     /// the path to save the result file
     #[arg(long)]
     output: Option<std::path::PathBuf>,
@@ -48,15 +61,27 @@ struct Args {
 
 fn main() {
   let args = Args::parse();
-  let continuations: HashMap<Field, HashMap<Piece, Vec<Field>>> =
-    bincode::deserialize_from(std::fs::File::open(args.continuation).unwrap()).unwrap();
+  let continuations = continuations::load(&args.continuation);
   eprintln!("{}", continuations.len());
 
-  let build_states = || FieldSequenceStates::<BagSequenceStates>::new(&continuations, args.preview, args.hold);
-  let num2state = build_states();
+  let sequence = BagSequenceStates::with_bag(args.preview, &args.bag);
+  let mut num2state = FieldSequenceStates::with_sequence(&continuations, args.hold, sequence);
+  if let Some(first_hold) = args.first_hold {
+    num2state = num2state.with_empty_hold(first_hold);
+  }
   eprintln!("{}", num2state.len());
 
-  let mut minimized = ParallelMinimizer::minimize(num2state).concrete();
+  let mut minimized = if args.mirror {
+    let mirrored = num2state.mirror().unwrap_or_else(|| {
+      Args::command()
+        .error(ErrorKind::ArgumentConflict, "the bag is not symmetric under mirroring")
+        .exit()
+    });
+    eprintln!("mirrored: {}", mirrored.len());
+    ParallelMinimizer::minimize(mirrored).concrete().compose()
+  } else {
+    ParallelMinimizer::minimize(num2state).concrete()
+  };
   report(&minimized);
 
   {
@@ -0,0 +1,96 @@
+use c4w::basics::{Field, Piece};
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+use itertools::Itertools;
+
+/// Print the best move of a position from the policy file saved by extract_policy or c4w.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the policy file
+  #[arg(long)]
+  policy: std::path::PathBuf,
+
+  /// The field, like `...X/X..X/X..X/X..X`
+  #[arg(long)]
+  field: Field,
+
+  /// The piece in the hold, if the policy holds
+  #[arg(long)]
+  hold: Option<Piece>,
+
+  /// The hold is empty, if the policy has the empty hold
+  #[arg(long, action, conflicts_with = "hold")]
+  empty_hold: bool,
+
+  /// The current piece followed by the preview, like `TIOS`
+  #[arg(long)]
+  queue: String,
+
+  /// The pieces left in the bag after the preview, like `SZJL`. The moves are averaged over
+  /// every compatible bag weighted by its probability if not given
+  #[arg(long, value_parser = BagSequenceStates::parse_bag)]
+  bag_left: Option<std::vec::Vec<usize>>
+}
+
+fn query<S: SequenceStates>(args: Args, policy: Policy<FieldSequenceStates<S>>) {
+  let original = &policy.original;
+  assert!(
+    original.hold() == (args.hold.is_some() || args.empty_hold),
+    "the hold does not match the policy"
+  );
+  let queue =
+    args.queue.chars().map(|piece| piece.to_string().parse::<Piece>().unwrap()).collect::<Vec<_>>();
+  let (&current, preview) = queue.split_first().expect("the queue is empty");
+  let left = args.bag_left;
+  assert!(left.is_none() || original.sequence().bag().is_some(), "the policy has no bags");
+  let hold = original.hold_index(args.hold);
+  let probabilities = original.sequence().stationary();
+  let found =
+    original.find_branches(&args.field, hold, current, preview, left.as_deref(), &probabilities);
+  if found.is_empty() {
+    panic!("the position is not in the policy");
+  }
+
+  let (next, piece, placement, value) = if let [(state, branch, _)] = &found[..] {
+    let (next, piece, placement) = policy.choose(state, *branch).expect("no move");
+    (next, piece, placement, policy.value(&next) + placement.is_some() as u8 as f64)
+  } else {
+    let branches =
+      found.iter().map(|&(_, branch, probability)| (branch, probability)).collect_vec();
+    policy
+      .average_moves(&branches)
+      .into_iter()
+      .reduce(|best, next| if next.3 > best.3 { next } else { best })
+      .expect("no move")
+  };
+  let (field, hold, _) = next;
+  println!("compatible states: {}", found.len());
+  if piece != current || placement.is_none() {
+    println!("hold {:?}", current);
+  }
+  if let Some(placement) = placement {
+    println!("place {:?} at {}", piece, placement);
+  }
+  if original.hold() {
+    match original.held(hold) {
+      Some(piece) => println!("hold after: {:?}", piece),
+      None => println!("hold after: empty")
+    }
+  }
+  println!("{}", original.fields()[field]);
+  println!("expected combos: {:.6}", value);
+}
+
+fn main() {
+  let args = Args::parse();
+  let policy: PolicyFile =
+    bincode::deserialize_from(std::fs::File::open(&args.policy).unwrap()).unwrap();
+  match policy {
+    PolicyFile::Random(policy) => query(args, policy),
+    PolicyFile::Bag(policy) => query(args, policy),
+    PolicyFile::History(policy) => query(args, policy),
+    PolicyFile::Automaton(policy) => query(args, policy)
+  }
+}
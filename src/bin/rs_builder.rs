@@ -1,183 +1,505 @@
 use c4w::basics::{Field, Piece, PIECES};
-use std::collections::{HashMap, VecDeque};
+use c4w::continuations::{Placement, Spin};
+use c4w::rotation::{PieceRotation, RotationSystem};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
 
-#[derive(Copy, Clone)]
-struct RotatedPiece([u8; 4]);
+// the cells of a piece inside a well as wide as the field
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+struct RotatedPiece(Field);
 
 impl RotatedPiece {
-  #[allow(dead_code)]
+  fn new(cells: &[(i32, i32)], x: i32, y: i32, width: usize, height: usize) -> Option<Self> {
+    let mut piece = Field::empty(width);
+    for &(cell_x, cell_y) in cells {
+      let (cell_x, cell_y) = (cell_x + x, cell_y + y);
+      if !(0..width as i32).contains(&cell_x) || !(0..height as i32).contains(&cell_y) {
+        return None;
+      }
+      piece.set(cell_x as usize, cell_y as usize);
+    }
+    Some(RotatedPiece(piece))
+  }
+  fn left_column(&self) -> u128 {
+    (0..self.0.max_height()).fold(0, |acc, y| acc | 1 << (y * self.0.width()))
+  }
+  fn shift(&self, cells: u128) -> Self {
+    RotatedPiece(Field { cells, ..self.0 })
+  }
   fn move_left(&self) -> Option<Self> {
-    if self.0[0] != 0 {
+    if self.0.cells & self.left_column() != 0 {
       None
     } else {
-      Some(RotatedPiece([self.0[1], self.0[2], self.0[3], 0]))
+      Some(self.shift(self.0.cells >> 1))
     }
   }
   fn move_right(&self) -> Option<Self> {
-    if self.0[3] != 0 {
+    if self.0.cells & (self.left_column() << (self.0.width() - 1)) != 0 {
       None
     } else {
-      Some(RotatedPiece([0, self.0[0], self.0[1], self.0[2]]))
+      Some(self.shift(self.0.cells << 1))
     }
   }
   fn move_down(&self) -> Option<Self> {
-    if self.0.iter().any(|&x| x & 1 != 0) {
+    if self.0.row(0) != 0 {
       None
     } else {
-      Some(RotatedPiece([self.0[0] >> 1, self.0[1] >> 1, self.0[2] >> 1, self.0[3] >> 1]))
+      Some(self.shift(self.0.cells >> self.0.width()))
+    }
+  }
+  fn move_up(&self, height: usize) -> Option<Self> {
+    if self.0.row(height - 1) != 0 {
+      None
+    } else {
+      Some(self.shift(self.0.cells << self.0.width()))
     }
   }
 }
 
 #[derive(Copy, Clone)]
-enum LineClear {
-  Harddrop(Field),
-  Softdrop(Field, RotatedPiece, Field)
+struct PieceState {
+  orientation: usize,
+  x: i32,
+  y: i32,
+  piece: RotatedPiece
+}
+
+impl PieceState {
+  fn move_down(&self) -> Option<Self> {
+    self.piece.move_down().map(|piece| PieceState { y: self.y - 1, piece, ..*self })
+  }
 }
 
-fn get_piece(p: &Piece) -> Vec<RotatedPiece> {
+// a resting position that clears lines, and the field after clearing them
+#[derive(Copy, Clone)]
+struct LineClear {
+  state: PieceState,
+  softdrop: bool,
+  lines: usize,
+  field: Field
+}
+
+impl LineClear {
+  fn placement(&self, spin: Spin) -> Placement {
+    Placement {
+      orientation: self.state.orientation as u8,
+      x: self.state.x as i8,
+      y: self.state.y as i8,
+      softdrop: self.softdrop,
+      lines: self.lines as u8,
+      spin
+    }
+  }
+}
+
+// every shape of the piece at the top of the field, in every column
+fn get_piece(rotation: &PieceRotation, width: usize, height: usize) -> Vec<PieceState> {
   let mut result = Vec::new();
-  let mut push_until_right_most = |shape: [u8; 4]| {
-    let mut piece = RotatedPiece(shape);
+  for orientation in 0..4 {
+    let cells = rotation.cells(orientation);
+    let left = cells.iter().map(|&(x, _)| x).min().unwrap();
+    let top = cells.iter().map(|&(_, y)| y).max().unwrap();
+    let (mut x, y) = (-left, height as i32 - 1 - top);
+    let Some(mut piece) = RotatedPiece::new(&cells, x, y, width, height) else {
+      continue;
+    };
+    if result.iter().any(|state: &PieceState| state.piece == piece) {
+      continue;
+    }
     loop {
-      result.push(piece);
+      result.push(PieceState { orientation, x, y, piece });
       let p = piece.move_right();
       if let Some(p) = p {
         piece = p;
+        x += 1;
       } else {
         break;
       }
     }
-  };
-  match p {
-    Piece::I => {
-      push_until_right_most([0b10000000, 0b10000000, 0b10000000, 0b10000000]);
-      push_until_right_most([0b11110000, 0b00000000, 0b00000000, 0b00000000]);
-    }
-    Piece::O => {
-      push_until_right_most([0b11000000, 0b11000000, 0b00000000, 0b00000000]);
-    }
-    Piece::T => {
-      push_until_right_most([0b10000000, 0b11000000, 0b10000000, 0b00000000]);
-      push_until_right_most([0b11100000, 0b01000000, 0b00000000, 0b00000000]);
-      push_until_right_most([0b01000000, 0b11000000, 0b01000000, 0b00000000]);
-      push_until_right_most([0b01000000, 0b11100000, 0b00000000, 0b00000000]);
-    }
-    Piece::S => {
-      push_until_right_most([0b01000000, 0b11000000, 0b10000000, 0b00000000]);
-      push_until_right_most([0b11000000, 0b01100000, 0b00000000, 0b00000000]);
-    }
-    Piece::Z => {
-      push_until_right_most([0b10000000, 0b11000000, 0b01000000, 0b00000000]);
-      push_until_right_most([0b01100000, 0b11000000, 0b00000000, 0b00000000]);
-    }
-    Piece::J => {
-      push_until_right_most([0b11000000, 0b01000000, 0b01000000, 0b00000000]);
-      push_until_right_most([0b11100000, 0b10000000, 0b00000000, 0b00000000]);
-      push_until_right_most([0b10000000, 0b10000000, 0b11000000, 0b00000000]);
-      push_until_right_most([0b00100000, 0b11100000, 0b00000000, 0b00000000]);
-    }
-    Piece::L => {
-      push_until_right_most([0b01000000, 0b01000000, 0b11000000, 0b00000000]);
-      push_until_right_most([0b11100000, 0b00100000, 0b00000000, 0b00000000]);
-      push_until_right_most([0b11000000, 0b10000000, 0b10000000, 0b00000000]);
-      push_until_right_most([0b10000000, 0b11100000, 0b00000000, 0b00000000]);
-    }
   }
   result
 }
 
 trait FieldDummy {
+  #[allow(dead_code)]
   fn flip_vertically(&self) -> Self;
   fn clearable(&self) -> bool;
   fn clear_line(&self) -> (usize, Self);
   fn overlap(&self, piece: &RotatedPiece) -> bool;
   fn put(&self, piece: &RotatedPiece) -> Self;
-  fn possible_positions(&self, piece: &RotatedPiece) -> Vec<LineClear>;
+  fn possible_positions(&self, piece: &PieceState) -> Vec<LineClear>;
+  fn reachable(
+    &self,
+    piece: Piece,
+    rotation: &PieceRotation,
+    height: usize
+  ) -> HashMap<RotatedPiece, Spin>;
+  fn filled(&self, x: i32, y: i32) -> bool;
+  fn spin(
+    &self,
+    piece: Piece,
+    shape: &[(i32, i32)],
+    state: &PieceState,
+    kick: (i32, i32),
+    height: usize
+  ) -> Spin;
 }
 
 impl FieldDummy for Field {
   fn flip_vertically(&self) -> Self {
-    Field([self.0[3], self.0[2], self.0[1], self.0[0]])
+    let mut field = Field::empty(self.width());
+    for y in 0..self.height() {
+      for x in 0..self.width() {
+        if self.get(x, y) {
+          field.set(self.width() - 1 - x, y);
+        }
+      }
+    }
+    field
   }
   fn clearable(&self) -> bool {
-    let culmulated = self.0.iter().fold(!0, |acc, &x| acc & x);
-    culmulated != 0
+    (0..self.height()).any(|y| self.row(y) == self.full_row())
   }
   fn clear_line(&self) -> (usize, Self) {
     let mut line_count = 0;
-    let mut field = *self;
-    if !field.clearable() {
-      return (0, field);
-    }
-    let culmulated = field.0.iter().fold(!0, |acc, &x| acc & x);
-    for i in 0..8 {
-      if culmulated & (1 << i) == 0 {
-        continue;
-      }
-      let mask: u8 = (!0) << (i - line_count);
-      for j in 0..4 {
-        field.0[j] = field.0[j] & !mask | (field.0[j] >> 1) & mask;
+    let mut field = Field::empty(self.width());
+    for y in 0..self.height() {
+      if self.row(y) == self.full_row() {
+        line_count += 1;
+      } else {
+        field.cells |= self.row(y) << ((y - line_count) * self.width());
       }
-      line_count += 1;
     }
     (line_count, field)
   }
   fn overlap(&self, piece: &RotatedPiece) -> bool {
-    for i in 0..4 {
-      if self.0[i] & piece.0[i] != 0 {
-        return true;
-      }
-    }
-    false
+    self.cells & piece.0.cells != 0
   }
   fn put(&self, piece: &RotatedPiece) -> Self {
-    let mut field = *self;
-    for i in 0..4 {
-      field.0[i] |= piece.0[i];
-    }
-    field
+    Field { cells: self.cells | piece.0.cells, ..*self }
   }
-  fn possible_positions(&self, piece: &RotatedPiece) -> Vec<LineClear> {
-    let mut piece = *piece;
+  // every resting position of the piece along its column that clears lines
+  fn possible_positions(&self, piece: &PieceState) -> Vec<LineClear> {
+    let mut state = *piece;
     let mut result_fields = Vec::new();
-    let mut last_push = false;
     let mut harddrop = true;
     loop {
-      let mut this_push = false;
-      if !self.overlap(&piece) {
-        if last_push {
-          result_fields.pop();
-        }
-        let field = self.put(&piece);
+      let below = state.move_down();
+      if self.overlap(&state.piece) {
+        harddrop = false;
+      } else if below.map_or(true, |p| self.overlap(&p.piece)) {
+        let field = self.put(&state.piece);
         if field.clearable() {
-          let (_, field) = field.clear_line();
-          result_fields.push(if harddrop {
-            LineClear::Harddrop(field)
-          } else {
-            LineClear::Softdrop(*self, piece, field)
-          });
-          this_push = true;
+          let (lines, field) = field.clear_line();
+          result_fields.push(LineClear { state, softdrop: !harddrop, lines, field });
         }
-      } else {
-        harddrop = false;
       }
-      last_push = this_push;
-      let p = piece.move_down();
-      if let Some(p) = p {
-        piece = p;
+      if let Some(p) = below {
+        state = p;
       } else {
         break;
       }
     }
     result_fields
   }
+  // every position the piece can be moved to from above the field,
+  // by shifting, soft dropping and rotating with kicks,
+  // with the best spin it can be rotated into the position with
+  fn reachable(
+    &self,
+    piece: Piece,
+    rotation: &PieceRotation,
+    height: usize
+  ) -> HashMap<RotatedPiece, Spin> {
+    let shapes = [0, 1, 2, 3].map(|orientation| rotation.cells(orientation));
+    let (box_width, box_height) = rotation.size();
+    // the search goes on above the field, where the piece spawns
+    let area = height + box_height as usize;
+    let place = |orientation: usize, x: i32, y: i32| {
+      RotatedPiece::new(&shapes[orientation], x, y, self.width(), area)
+        .filter(|piece| !self.overlap(piece))
+        .map(|piece| PieceState { orientation, x, y, piece })
+    };
+    let mut visited = HashSet::new();
+    let mut spins = HashMap::new();
+    // the board is open above the stack, so the piece comes down into the well in any
+    // orientation and column it fits in
+    let mut queue: VecDeque<_> = (0..4)
+      .flat_map(|orientation| {
+        (-box_width..self.width() as i32).map(move |x| (orientation, x))
+      })
+      .filter_map(|(orientation, x)| place(orientation, x, height as i32))
+      .collect();
+    while let Some(state) = queue.pop_front() {
+      if !visited.insert((state.orientation, state.x, state.y)) {
+        continue;
+      }
+      let PieceState { orientation, x, y, piece: rotated } = state;
+      let shifted = [
+        rotated.move_left().map(|piece| PieceState { x: x - 1, piece, ..state }),
+        rotated.move_right().map(|piece| PieceState { x: x + 1, piece, ..state }),
+        rotated.move_down().map(|piece| PieceState { y: y - 1, piece, ..state })
+      ];
+      queue.extend(shifted.into_iter().flatten().filter(|next| !self.overlap(&next.piece)));
+      for (target, kicks) in rotation.rotations(orientation) {
+        let kicked = kicks
+          .iter()
+          .find_map(|&(dx, dy)| place(target, x + dx, y + dy).map(|next| (next, (dx, dy))));
+        if let Some((next, kick)) = kicked {
+          let spin = self.spin(piece, &shapes[target], &next, kick, area);
+          let known = spins.entry((target, next.x, next.y)).or_insert(spin);
+          *known = spin.max(*known);
+          queue.push_back(next);
+        }
+      }
+    }
+    let mut result = HashMap::new();
+    for (orientation, x, y) in visited {
+      let piece = RotatedPiece::new(&shapes[orientation], x, y, self.width(), area).unwrap();
+      let spin = spins.get(&(orientation, x, y)).copied().unwrap_or(Spin::None);
+      let known = result.entry(piece).or_insert(spin);
+      *known = spin.max(*known);
+    }
+    result
+  }
+  // whether the cell is filled, or out of the walls or the floor
+  fn filled(&self, x: i32, y: i32) -> bool {
+    x < 0
+      || x >= self.width() as i32
+      || y < 0
+      || (y < self.max_height() as i32 && self.get(x as usize, y as usize))
+  }
+  // the spin of the piece rotated into `state` with the kick,
+  // by the 3-corner rule for T and by immobility for the other pieces
+  fn spin(
+    &self,
+    piece: Piece,
+    shape: &[(i32, i32)],
+    state: &PieceState,
+    (dx, dy): (i32, i32),
+    height: usize
+  ) -> Spin {
+    let cells = shape.iter().map(|&(x, y)| (x + state.x, y + state.y)).collect::<Vec<_>>();
+    if piece != Piece::T {
+      let blocked = |moved: Option<RotatedPiece>| moved.map_or(true, |moved| self.overlap(&moved));
+      let rotated = state.piece;
+      // the ceiling is open, unlike the walls
+      return if blocked(rotated.move_left())
+        && blocked(rotated.move_right())
+        && rotated.move_up(height).is_some_and(|moved| self.overlap(&moved))
+      {
+        Spin::AllSpin
+      } else {
+        Spin::None
+      };
+    }
+    let cells = &cells;
+    let neighbours = move |(x, y): (i32, i32)| {
+      [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .filter(move |&(nx, ny)| cells.contains(&(x + nx, y + ny)))
+    };
+    let center = *cells.iter().find(|&&cell| neighbours(cell).count() == 3).unwrap();
+    // the side the T points to, where its stem is
+    let (fx, fy) = neighbours(center)
+      .find(|&(nx, ny)| !cells.contains(&(center.0 - nx, center.1 - ny)))
+      .unwrap();
+    let corner = |front: i32, side: i32| {
+      self.filled(center.0 + front * fx + side * fy, center.1 + front * fy + side * fx)
+    };
+    let front = [corner(1, 1), corner(1, -1)];
+    let back = [corner(-1, 1), corner(-1, -1)];
+    if front.iter().chain(back.iter()).filter(|&&filled| filled).count() < 3 {
+      Spin::None
+    } else if front.iter().all(|&filled| filled) || (dx.abs(), dy.abs()) == (1, 2) {
+      Spin::TSpin
+    } else {
+      Spin::TSpinMini
+    }
+  }
+}
+
+// answers to the soft-drop questions, one `field | piece | y/n` line per question,
+// with the columns of the field and the piece in hex
+struct Answers {
+  known: HashMap<(Field, RotatedPiece), bool>,
+  record: Option<std::fs::File>,
+  // the recorded answers replayed without asking, if given
+  replay: Option<std::path::PathBuf>,
+  height: usize
+}
+
+impl Answers {
+  fn new(
+    cache: Option<&std::path::Path>,
+    answers: Option<&std::path::Path>,
+    height: usize
+  ) -> Self {
+    let path = answers.or(cache);
+    let known = path
+      .filter(|path| answers.is_some() || path.exists())
+      .map(|path| {
+        let content = std::fs::read_to_string(path).unwrap();
+        let lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        lines
+          .map(|(number, line)| {
+            parse_answer(line).unwrap_or_else(|error| {
+              Args::command()
+                .error(
+                  ErrorKind::InvalidValue,
+                  format!("{} at line {} of {}", error, number + 1, path.display())
+                )
+                .exit()
+            })
+          })
+          .collect()
+      })
+      .unwrap_or_default();
+    let record =
+      cache.map(|path| std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap());
+    Self { known, record, replay: answers.map(|path| path.to_path_buf()), height }
+  }
+  fn ask(&mut self, field: &Field, piece: &RotatedPiece) -> bool {
+    if let Some(&answer) = self.known.get(&(*field, *piece)) {
+      return answer;
+    }
+    print(field, Some(piece), self.height);
+    if let Some(path) = &self.replay {
+      Args::command()
+        .error(
+          ErrorKind::ValueValidation,
+          format!(
+            "no answer for the placement above in {}, to be added as `{} | {} | y/n`",
+            path.display(),
+            hex(field),
+            hex(&piece.0)
+          )
+        )
+        .exit();
+    }
+    let mut buffer = String::new();
+    std::io::stdin().read_line(&mut buffer).unwrap();
+    let answer = buffer.trim() == "y" || buffer.trim() == "Y";
+    self.known.insert((*field, *piece), answer);
+    if let Some(record) = self.record.as_mut() {
+      writeln!(record, "{} | {} | {}", hex(field), hex(&piece.0), if answer { 'y' } else { 'n' })
+        .unwrap();
+    }
+    answer
+  }
+}
+
+fn hex(field: &Field) -> String {
+  let column =
+    |x| (0..field.height()).filter(|&y| field.get(x, y)).fold(0_u128, |acc, y| acc | 1 << y);
+  (0..field.width()).map(|x| format!("{:02x}", column(x))).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_answer(line: &str) -> Result<((Field, RotatedPiece), bool), String> {
+  let invalid = || format!("invalid answer {:?}", line);
+  let parse_columns = |part: &str| {
+    let columns = part
+      .split_whitespace()
+      .map(|column| u128::from_str_radix(column, 16).map_err(|_| invalid()))
+      .collect::<Result<Vec<_>, _>>()?;
+    if columns.is_empty() || columns.len() > Field::MAX_CELLS {
+      return Err(invalid());
+    }
+    let mut field = Field::empty(columns.len());
+    for (x, column) in columns.into_iter().enumerate() {
+      if column.checked_shr(field.max_height() as u32).unwrap_or(0) != 0 {
+        return Err(invalid());
+      }
+      for y in (0..field.max_height()).filter(|&y| column & (1 << y) != 0) {
+        field.set(x, y);
+      }
+    }
+    Ok(field)
+  };
+  match line.split('|').map(str::trim).collect::<Vec<_>>()[..] {
+    [field, piece, answer] => {
+      let answer = match answer {
+        "y" | "Y" => true,
+        "n" | "N" => false,
+        _ => return Err(invalid())
+      };
+      let (field, piece) = (parse_columns(field)?, parse_columns(piece)?);
+      if field.width() != piece.width() {
+        return Err(invalid());
+      }
+      Ok(((field, RotatedPiece(piece)), answer))
+    }
+    _ => Err(invalid())
+  }
+}
+
+/// Build the continuation file by a BFS over the fields.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// Ask whether each soft-drop placement is reachable instead of searching with kicks
+  #[arg(long, action)]
+  ask: bool,
+
+  /// The path to the rotation system description, SRS if not given
+  #[arg(long, value_parser = |path: &str| RotationSystem::load(path.as_ref()))]
+  rotation_system: Option<RotationSystem>,
+
+  /// The path to the file caching the answers, loaded at startup and appended to as questions are answered (implies --ask)
+  #[arg(long, conflicts_with = "answers")]
+  answer_cache: Option<std::path::PathBuf>,
+
+  /// The path to the recorded answers, failing on any question without an answer (implies --ask)
+  #[arg(long)]
+  answers: Option<std::path::PathBuf>,
+
+  /// A starting field, with rows from top to bottom separated by `/`, like `...X/X..X/X..X/X..X`
+  #[arg(long)]
+  start: Vec<Field>,
+
+  /// The path to a file of starting fields, one row per line, separated by blank lines
+  #[arg(long)]
+  start_file: Option<std::path::PathBuf>,
+
+  /// The number of rows in the well that pieces can move in
+  #[arg(long, default_value_t = 8)]
+  height: usize
 }
 
 fn main() {
+  let args = Args::parse();
+  let rotation_system = args.rotation_system.unwrap_or_else(RotationSystem::srs);
+  let ask = args.ask || args.answer_cache.is_some() || args.answers.is_some();
+  let mut answers =
+    Answers::new(args.answer_cache.as_deref(), args.answers.as_deref(), args.height);
   let mut continuation = HashMap::new();
-  let mut queue = VecDeque::new();
-  queue.push_back(Field([0b00000111, 0b00000000, 0b00000000, 0b00001111]));
+  let mut queue: VecDeque<Field> = args.start.iter().cloned().collect();
+  if let Some(path) = args.start_file.as_deref() {
+    let content = std::fs::read_to_string(path).unwrap();
+    let mut block = vec![];
+    for line in content.lines().chain(std::iter::once("")) {
+      if !line.trim().is_empty() {
+        block.push(line);
+      } else if !block.is_empty() {
+        queue.push_back(block.join("\n").parse().unwrap());
+        block.clear();
+      }
+    }
+  }
+  if queue.is_empty() {
+    queue.push_back("...X/X..X/X..X/X..X".parse().unwrap());
+  }
+  let width = queue[0].width();
+  assert!(queue.iter().all(|field| field.width() == width), "starting fields of different widths");
+  assert!(queue.iter().all(|field| field.height() <= args.height), "starting fields too tall");
+  // the pieces spawn above the well
+  let box_height = PIECES.iter().map(|piece| rotation_system.piece(piece).size().1).max().unwrap();
+  assert!(
+    args.height + box_height as usize <= Field::empty(width).max_height(),
+    "the well is too tall"
+  );
   while !queue.is_empty() {
     let field = queue.pop_front().unwrap();
     if continuation.contains_key(&field) {
@@ -186,23 +508,17 @@ fn main() {
     let mut nexts = HashMap::new();
     for piece in PIECES.iter() {
       let mut v = vec![];
-      for rotated_piece in get_piece(piece) {
-        for position in field.possible_positions(&rotated_piece) {
-          if let Some(new_field) = match position {
-            LineClear::Harddrop(new_field) => Some(new_field),
-            LineClear::Softdrop(old_field, piece, new_field) => {
-              let mut buffer = String::new();
-              print(&old_field, Some(&piece));
-              std::io::stdin().read_line(&mut buffer).unwrap();
-              if buffer.trim() == "y" || buffer.trim() == "Y" {
-                Some(new_field)
-              } else {
-                None
-              }
-            }
-          } {
-            v.push(new_field);
-            queue.push_back(new_field);
+      let rotation = rotation_system.piece(piece);
+      let reachable = field.reachable(*piece, rotation, args.height);
+      for piece_state in get_piece(rotation, width, args.height) {
+        for position in field.possible_positions(&piece_state) {
+          let piece = position.state.piece;
+          if !position.softdrop
+            || if ask { answers.ask(&field, &piece) } else { reachable.contains_key(&piece) }
+          {
+            let spin = reachable.get(&piece).copied().unwrap_or(Spin::None);
+            v.push((position.field, position.placement(spin)));
+            queue.push_back(position.field);
           }
         }
       }
@@ -213,34 +529,64 @@ fn main() {
   bincode::serialize_into(std::io::stdout(), &continuation).unwrap();
 }
 
-fn print(field: &Field, piece: Option<&RotatedPiece>) {
-  let mut result = [["  "; 4]; 8];
-  for i in 0..4 {
-    for (j, item) in result.iter_mut().enumerate() {
-      if field.0[i] & (1 << j) != 0 {
-        item[i] = "XX";
-      }
+fn print(field: &Field, piece: Option<&RotatedPiece>, height: usize) {
+  for y in (0..height).rev() {
+    eprint!("{} |", y);
+    for x in 0..field.width() {
+      eprint!("{}", match (field.get(x, y), piece.is_some_and(|piece| piece.0.get(x, y))) {
+        (false, false) => "  ",
+        (true, false) => "XX",
+        (false, true) => "[]",
+        (true, true) => "**"
+      });
     }
+    eprintln!("|");
   }
-  if let Some(piece) = piece {
-    for i in 0..4 {
-      for (j, item) in result.iter_mut().enumerate() {
-        if piece.0[i] & (1 << j) != 0 {
-          if item[i] == "  " {
-            item[i] = "[]";
-          } else {
-            item[i] = "**";
-          }
-        }
-      }
-    }
+  eprintln!("  +{}+", "--".repeat(field.width()));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn piece(cells: &str) -> RotatedPiece {
+    RotatedPiece(cells.parse().unwrap())
   }
-  for i in (0..8).rev() {
-    eprint!("{} |", i);
-    for j in 0..4 {
-      eprint!("{}", result[i][j]);
-    }
-    eprintln!("|");
+
+  #[test]
+  fn the_pieces_spawn_above_the_stack() {
+    let rotation_system = RotationSystem::srs();
+    let field: Field = "XX../XX../X.../....".parse().unwrap();
+    let reachable = field.reachable(Piece::O, rotation_system.piece(&Piece::O), 4);
+    assert!(reachable.contains_key(&piece("..../..../.XX./.XX.")));
+    let field = Field::empty(3);
+    let reachable = field.reachable(Piece::I, rotation_system.piece(&Piece::I), 4);
+    assert!(reachable.contains_key(&piece("X../X../X../X..")));
+  }
+
+  #[test]
+  fn the_ceiling_does_not_block_spins() {
+    let rotation = RotationSystem::srs().piece(&Piece::O).clone();
+    let shape = rotation.cells(0);
+    let spin = |field: &str, bottom: i32| {
+      let field: Field = field.parse().unwrap();
+      // the O in the middle columns with its bottom at the row
+      let x = 1 - shape.iter().map(|&(x, _)| x).min().unwrap();
+      let y = bottom - shape.iter().map(|&(_, y)| y).min().unwrap();
+      let piece = RotatedPiece::new(&shape, x, y, 4, 4).unwrap();
+      field.spin(Piece::O, &shape, &PieceState { orientation: 0, x, y, piece }, (0, 0), 4)
+    };
+    assert_eq!(spin("X..X/X..X/X..X/X..X", 2), Spin::None);
+    assert_eq!(spin("XXXX/X..X/X..X/X..X", 1), Spin::AllSpin);
+  }
+
+  #[test]
+  fn answers_round_trip() {
+    let field: Field = "X..X/XX.X".parse().unwrap();
+    let line = format!("{} | {} | y", hex(&field), hex(&field));
+    assert!(parse_answer(&line).unwrap() == ((field, RotatedPiece(field)), true));
+    assert!(parse_answer("03 01 | 01 | y").is_err());
+    assert!(parse_answer("03 01 00 03 | 00 06 00 00").is_err());
+    assert!(parse_answer("03 zz 00 03 | 00 06 00 00 | n").is_err());
   }
-  eprintln!("  +--------+");
 }
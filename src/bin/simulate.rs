@@ -0,0 +1,154 @@
+use average::{Estimate, Variance};
+use c4w::basics::Field;
+use c4w::evaluator::*;
+use c4w::states::*;
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Play the policy file saved by extract_policy or c4w on sampled piece sequences, and check the
+/// simulated combos against the values computed by value iteration.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the policy file
+  #[arg(long)]
+  policy: std::path::PathBuf,
+
+  /// The number of games
+  #[arg(long, default_value = "100000")]
+  games: usize,
+
+  /// The seed of the random pieces, the same seed plays the same games
+  #[arg(long, default_value = "0")]
+  seed: u64,
+
+  /// The field every game starts on, a random one if not given.
+  /// The hold and the sequence are random
+  #[arg(long)]
+  field: Option<Field>,
+
+  /// A class of states is flagged if its simulated mean is this many standard errors away
+  /// from its computed value
+  #[arg(long, default_value = "5")]
+  z: f64,
+
+  /// The classes visited in fewer games than this are not checked
+  #[arg(long, default_value = "100")]
+  min_visits: u64
+}
+
+// the values are computed up to this precision
+const EPS: f64 = 1e-6;
+
+// the classes of the states visited in a game, the last one where it ends, and the combos
+fn play<S: SequenceStates>(
+  policy: &Policy<FieldSequenceStates<S>>,
+  start: <FieldSequenceStates<S> as States>::State,
+  rng: &mut StdRng
+) -> (Vec<usize>, usize) {
+  let original = &policy.original;
+  let mut visits = vec![];
+  let mut combos = 0;
+  let mut state = start;
+  loop {
+    visits.push(policy.class(&state));
+    let branches = original.next_pieces(state).collect::<Vec<_>>();
+    let total = branches.iter().map(|branch| original.weight(branch)).sum::<u64>();
+    let mut roll = rng.random_range(0..total);
+    let branch = *branches
+      .iter()
+      .find(|branch| {
+        let weight = original.weight(branch);
+        roll.checked_sub(weight).map(|left| roll = left).is_none()
+      })
+      .unwrap();
+    match policy.choose(&state, branch) {
+      Some((next, _, placement)) => {
+        combos += placement.is_some() as usize;
+        state = next;
+      }
+      None => break
+    }
+  }
+  (visits, combos)
+}
+
+fn simulate<S: SequenceStates>(args: &Args, policy: Policy<FieldSequenceStates<S>>) {
+  let original = &policy.original;
+  let field = args.field.map(|field| {
+    original
+      .fields()
+      .iter()
+      .position(|f| *f == field)
+      .unwrap_or_else(|| panic!("{} is not in the policy", field.notation()))
+  });
+
+  let games = (0..args.games)
+    .into_par_iter()
+    .map(|game| {
+      let mut rng = StdRng::seed_from_u64(args.seed.wrapping_add(game as u64));
+      let start = (
+        field.unwrap_or_else(|| rng.random_range(0..original.fields().len())),
+        rng.random_range(0..original.holds()),
+        original.sequence().decode(rng.random_range(0..original.sequence().len())).unwrap()
+      );
+      (policy.value(&start), play(&policy, start, &mut rng))
+    })
+    .collect::<Vec<_>>();
+
+  let mut combos = Variance::new();
+  let mut computed = 0.;
+  // the first visit of each class in a game counts, with the moves left after it as the values
+  // count them, as the later visits of the same game are not independent of it
+  let mut classes = vec![Variance::new(); policy.values.len()];
+  for (value, (visits, game_combos)) in games.iter() {
+    combos.add(*game_combos as f64);
+    computed += value / games.len() as f64;
+    let mut visited = HashSet::new();
+    for (i, &class) in visits.iter().enumerate() {
+      if visited.insert(class) {
+        classes[class].add((visits.len() - 1 - i) as f64);
+      }
+    }
+  }
+  println!("games: {}", games.len());
+  println!("simulated combos: {:.6} ± {:.6} (95% CI)", combos.mean(), 1.96 * combos.error());
+  println!("computed combos: {:.6}", computed);
+
+  let mut checked = 0;
+  let mut flagged = 0;
+  for (class, simulated) in classes.iter().enumerate() {
+    if simulated.len() < args.min_visits {
+      continue;
+    }
+    checked += 1;
+    let value = policy.values[class];
+    if (simulated.mean() - value).abs() > args.z * simulated.error() + EPS {
+      flagged += 1;
+      println!(
+        "class {} ({} games): simulated {:.6} ± {:.6}, computed {:.6}",
+        class,
+        simulated.len(),
+        simulated.mean(),
+        simulated.error(),
+        value
+      );
+    }
+  }
+  println!("flagged classes: {} of {} checked", flagged, checked);
+}
+
+fn main() {
+  let args = Args::parse();
+  let policy: PolicyFile =
+    bincode::deserialize_from(std::fs::File::open(&args.policy).unwrap()).unwrap();
+  match policy {
+    PolicyFile::Random(policy) => simulate(&args, policy),
+    PolicyFile::Bag(policy) => simulate(&args, policy),
+    PolicyFile::History(policy) => simulate(&args, policy),
+    PolicyFile::Automaton(policy) => simulate(&args, policy)
+  }
+}
@@ -0,0 +1,54 @@
+use c4w::basics::Field;
+use c4w::continuations::{self, Entry};
+use clap::Parser;
+
+/// Check that a continuation file is well formed.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+  /// The path to the continuation file, in either the bincode or the text format
+  #[arg(long)]
+  continuation: std::path::PathBuf,
+
+  /// A starting field to search unreachable fields from, like `...X/X..X/X..X/X..X`
+  #[arg(long)]
+  start: Vec<Field>
+}
+
+fn print_results(title: &str, results: &[Entry]) {
+  println!("{}: {}", title, results.len());
+  for (field, piece, result, placement) in results {
+    println!("  {} {:?} -> {}@{}", field.notation(), piece, result.notation(), placement);
+  }
+}
+
+fn print_fields(title: &str, fields: &[Field]) {
+  println!("{}: {}", title, fields.len());
+  for field in fields {
+    println!("  {}", field.notation());
+  }
+}
+
+fn main() {
+  let args = Args::parse();
+  let continuations = continuations::load(&args.continuation);
+  let report = continuations::validate(&continuations, &args.start);
+  println!("fields: {}", continuations.len());
+  println!("missing pieces: {}", report.missing_pieces.len());
+  for (field, piece) in &report.missing_pieces {
+    println!("  {} {:?}", field.notation(), piece);
+  }
+  print_results("missing fields", &report.missing_fields);
+  if args.start.is_empty() {
+    println!("unreachable fields: not searched without --start");
+  } else {
+    print_fields("unreachable fields", &report.unreachable);
+  }
+  print_fields("fields without placements", &report.dead_ends);
+  print_results("duplicated results", &report.duplicates);
+  print_results("results of more than one placement", &report.duplicate_targets);
+  print_results("results with wrong cell counts", &report.cell_mismatches);
+  if !report.is_usable() {
+    std::process::exit(1);
+  }
+}
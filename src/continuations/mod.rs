@@ -0,0 +1,118 @@
+mod text;
+pub use text::*;
+mod validate;
+pub use validate::*;
+
+use crate::basics::{Field, Piece};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// the fields a placement of each piece can lead to from each field, as built by `rs_builder`
+pub type Continuations = HashMap<Field, HashMap<Piece, Vec<(Field, Placement)>>>;
+
+// how a piece is placed, before the lines are cleared
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Placement {
+  // the orientation in the rotation system, north, east, south and west in order
+  pub orientation: u8,
+  // the bottom-left corner of the bounding box, from the bottom-left cell of the field
+  pub x: i8,
+  pub y: i8,
+  // whether the piece cannot be hard dropped from the top of the well
+  pub softdrop: bool,
+  pub lines: u8,
+  pub spin: Spin
+}
+
+// the best spin the piece can be rotated into its position with,
+// ordered from the least to the most rewarded
+#[derive(Serialize, Deserialize, Debug, Hash, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Spin {
+  None,
+  // a piece other than T that cannot move left, right or up
+  AllSpin,
+  // a T with 3 corners filled, but not both in front of it
+  TSpinMini,
+  // a T with 3 corners filled, including both in front of it or kicked by (±1, ±2)
+  TSpin
+}
+
+impl Spin {
+  pub const NAMES: [(Spin, &'static str); 4] = [
+    (Spin::None, "none"),
+    (Spin::AllSpin, "allspin"),
+    (Spin::TSpinMini, "mini"),
+    (Spin::TSpin, "tspin")
+  ];
+}
+
+impl std::fmt::Display for Spin {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}", Spin::NAMES.iter().find(|(spin, _)| spin == self).unwrap().1)
+  }
+}
+
+impl std::str::FromStr for Spin {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Spin::NAMES
+      .iter()
+      .find(|(_, name)| name.eq_ignore_ascii_case(s.trim()))
+      .map(|(spin, _)| *spin)
+      .ok_or_else(|| format!("invalid spin {:?}", s))
+  }
+}
+
+// `orientation,x,y,hard|soft,lines[,spin]`, like `1,-1,0,hard,1` or `2,1,0,soft,2,tspin`
+impl std::fmt::Display for Placement {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let drop = if self.softdrop { "soft" } else { "hard" };
+    write!(f, "{},{},{},{},{}", self.orientation, self.x, self.y, drop, self.lines)?;
+    if self.spin != Spin::None {
+      write!(f, ",{}", self.spin)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::str::FromStr for Placement {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("invalid placement {:?}", s);
+    let (orientation, x, y, drop, lines, spin) = match s.split(',').collect::<Vec<_>>()[..] {
+      [orientation, x, y, drop, lines] => (orientation, x, y, drop, lines, Spin::None),
+      [orientation, x, y, drop, lines, spin] => (orientation, x, y, drop, lines, spin.parse()?),
+      _ => return Err(invalid())
+    };
+    let softdrop = match drop {
+      "hard" => false,
+      "soft" => true,
+      _ => return Err(invalid())
+    };
+    Ok(Placement {
+      orientation: orientation.parse().ok().filter(|&o| o < 4).ok_or_else(invalid)?,
+      x: x.parse().map_err(|_| invalid())?,
+      y: y.parse().map_err(|_| invalid())?,
+      softdrop,
+      lines: lines.parse().map_err(|_| invalid())?,
+      spin
+    })
+  }
+}
+
+// reads a continuation file in either the bincode or the text format
+pub fn load(path: &std::path::Path) -> Continuations {
+  let content = std::fs::read(path).unwrap();
+  match std::str::from_utf8(&content) {
+    Ok(text) if is_text(&content) => {
+      from_text(text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+    _ => bincode::deserialize(&content).unwrap()
+  }
+}
+
+// bincode starts with the length of the map as a little-endian u64, which always has zero bytes
+pub fn is_text(content: &[u8]) -> bool {
+  !content.contains(&0)
+}
@@ -0,0 +1,121 @@
+use super::*;
+use crate::basics::PIECES;
+
+// one block per field, in the order of the fields, like
+//
+// field ...X/X..X/X..X/X..X
+// I ...X/...X/X..X@1,1,0,hard,1
+// O X..X@0,1,0,hard,2
+// ...
+//
+// where each piece lists the fields its placements lead to, each followed by `@` and the placement,
+// and `#` starts a comment line
+pub fn to_text(continuations: &Continuations) -> String {
+  continuations
+    .iter()
+    .sorted_by_key(|&(field, _)| field)
+    .map(|(field, nexts)| {
+      std::iter::once(format!("field {}", field.notation()))
+        .chain(PIECES.iter().filter_map(|piece| {
+          nexts.get(piece).map(|fields| {
+            std::iter::once(format!("{:?}", piece))
+              .chain(
+                fields
+                  .iter()
+                  .map(|(field, placement)| format!("{}@{}", field.notation(), placement))
+              )
+              .join(" ")
+          })
+        }))
+        .map(|line| line + "\n")
+        .join("")
+    })
+    .join("\n")
+}
+
+pub fn from_text(text: &str) -> Result<Continuations, String> {
+  let mut continuations = Continuations::new();
+  let mut current = None;
+  for (number, line) in text.lines().enumerate() {
+    let error = |message: String| format!("line {}: {}", number + 1, message);
+    let mut tokens = line.split_whitespace();
+    let Some(head) = tokens.next().filter(|head| !head.starts_with('#')) else {
+      continue;
+    };
+    if head == "field" {
+      let field: Field = tokens
+        .exactly_one()
+        .map_err(|_| "expect one field".to_string())
+        .and_then(|token| token.parse())
+        .map_err(error)?;
+      if continuations.insert(field, HashMap::new()).is_some() {
+        return Err(error(format!("duplicated field {}", field.notation())));
+      }
+      current = Some(field);
+    } else {
+      let field = current.ok_or_else(|| error("piece before any field".to_string()))?;
+      let piece: Piece = head.parse().map_err(error)?;
+      let fields = tokens
+        .map(|token| {
+          let (field, placement) =
+            token.split_once('@').ok_or_else(|| format!("no placement in {:?}", token))?;
+          Ok((field.parse()?, placement.parse()?))
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(error)?;
+      if continuations.get_mut(&field).unwrap().insert(piece, fields).is_some() {
+        return Err(error(format!("duplicated piece {:?}", piece)));
+      }
+    }
+  }
+  Ok(continuations)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TEXT: &str = "field XXX.
+I ...X/...X/...X@1,1,0,hard,1
+O
+T
+S
+Z
+J
+L
+
+field ...X/...X/...X
+I
+O
+T
+S
+Z
+J ...X/X..X@0,0,-1,hard,1 XXX.@2,1,0,soft,2,tspin
+L
+";
+
+  #[test]
+  fn round_trip() {
+    let continuations = from_text(TEXT).unwrap();
+    assert_eq!(continuations.len(), 2);
+    let (field, placement) = continuations[&"XXX.".parse().unwrap()][&Piece::I][0];
+    assert_eq!(field, "...X/...X/...X".parse().unwrap());
+    assert_eq!(placement, Placement {
+      orientation: 1,
+      x: 1,
+      y: 0,
+      softdrop: false,
+      lines: 1,
+      spin: Spin::None
+    });
+    assert_eq!(to_text(&continuations), TEXT);
+    assert_eq!(from_text(&to_text(&continuations)).unwrap(), continuations);
+  }
+
+  #[test]
+  fn errors() {
+    assert_eq!(from_text("# comment\nI").unwrap_err(), "line 2: piece before any field");
+    assert!(from_text("field XXX.\nI ...X/...X/...X").unwrap_err().starts_with("line 2:"));
+    assert!(from_text("field XXX.\nfield XXX.").unwrap_err().starts_with("line 2:"));
+  }
+}
@@ -0,0 +1,149 @@
+use super::*;
+use crate::basics::PIECES;
+use std::collections::{HashSet, VecDeque};
+
+// a result of a piece from a field
+pub type Entry = (Field, Piece, Field, Placement);
+
+#[derive(Default, Debug)]
+pub struct Report {
+  // pieces that are not listed for a field
+  pub missing_pieces: Vec<(Field, Piece)>,
+  // results that are not listed as fields
+  pub missing_fields: Vec<Entry>,
+  // fields that cannot be reached from the starting fields
+  pub unreachable: Vec<Field>,
+  // fields where no piece has a placement that clears lines
+  pub dead_ends: Vec<Field>,
+  // results listed more than once for the same piece
+  pub duplicates: Vec<Entry>,
+  // results reached by more than one placement of the same piece, which is fine when the
+  // placements differ in their spins or drops
+  pub duplicate_targets: Vec<Entry>,
+  // results whose cells are not the cells of the field plus 4, minus the cleared lines
+  pub cell_mismatches: Vec<Entry>
+}
+
+impl Report {
+  // whether `FieldSequenceStates` can be built from the continuations
+  pub fn is_usable(&self) -> bool {
+    self.missing_pieces.is_empty() && self.missing_fields.is_empty()
+  }
+  pub fn is_clean(&self) -> bool {
+    self.is_usable()
+      && self.unreachable.is_empty()
+      && self.dead_ends.is_empty()
+      && self.duplicates.is_empty()
+      && self.cell_mismatches.is_empty()
+  }
+}
+
+// unreachable fields are only searched if some starting fields are given
+pub fn validate(continuations: &Continuations, starts: &[Field]) -> Report {
+  let mut report = Report::default();
+  for (&field, nexts) in continuations.iter().sorted_by_key(|&(field, _)| field) {
+    for piece in PIECES.iter() {
+      let Some(results) = nexts.get(piece) else {
+        report.missing_pieces.push((field, *piece));
+        continue;
+      };
+      let mut seen = HashSet::new();
+      let mut targets = HashSet::new();
+      for &(result, placement) in results {
+        let entry = (field, *piece, result, placement);
+        if !continuations.contains_key(&result) {
+          report.missing_fields.push(entry);
+        }
+        if !seen.insert((result, placement)) {
+          report.duplicates.push(entry);
+        } else if !targets.insert(result) {
+          report.duplicate_targets.push(entry);
+        }
+        if result.width != field.width
+          || placement.lines == 0
+          || field.count() + 4 != result.count() + placement.lines as usize * field.width()
+        {
+          report.cell_mismatches.push(entry);
+        }
+      }
+    }
+    if nexts.values().all(|results| results.is_empty()) {
+      report.dead_ends.push(field);
+    }
+  }
+  if !starts.is_empty() {
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<Field> = starts.iter().cloned().collect();
+    while let Some(field) = queue.pop_front() {
+      if !visited.insert(field) {
+        continue;
+      }
+      if let Some(nexts) = continuations.get(&field) {
+        queue.extend(nexts.values().flatten().map(|&(next, _)| next));
+      }
+    }
+    report.unreachable =
+      continuations.keys().filter(|field| !visited.contains(field)).cloned().sorted().collect();
+  }
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn report() {
+    let continuations = from_text(
+      "field XXX.
+I ...X/...X/...X@1,1,0,hard,1 ...X/...X/...X@1,1,0,hard,1 XXX.@1,1,0,hard,1
+O XX../XXX.@0,1,0,hard,1
+T
+S
+Z
+J
+L
+
+field ...X/...X/...X
+I XXX.@1,1,0,hard,1 XXX.@1,0,1,soft,1,allspin
+
+field X..X/X..X
+I
+O
+T
+S
+Z
+J
+L
+"
+    )
+    .unwrap();
+    let field = |notation: &str| notation.parse::<Field>().unwrap();
+    let placement = |notation: &str| notation.parse::<Placement>().unwrap();
+    let report = validate(&continuations, &[field("XXX.")]);
+    let missing_pieces = PIECES[1..].iter().map(|&piece| (field("...X/...X/...X"), piece));
+    assert_eq!(report.missing_pieces, missing_pieces.collect::<Vec<_>>());
+    assert_eq!(report.missing_fields, [(
+      field("XXX."),
+      Piece::O,
+      field("XX../XXX."),
+      placement("0,1,0,hard,1")
+    )]);
+    assert_eq!(report.unreachable, [field("X..X/X..X")]);
+    assert_eq!(report.dead_ends, [field("X..X/X..X")]);
+    assert_eq!(report.duplicates, [(
+      field("XXX."),
+      Piece::I,
+      field("...X/...X/...X"),
+      placement("1,1,0,hard,1")
+    )]);
+    assert_eq!(report.duplicate_targets, [(
+      field("...X/...X/...X"),
+      Piece::I,
+      field("XXX."),
+      placement("1,0,1,soft,1,allspin")
+    )]);
+    assert_eq!(report.cell_mismatches, report.missing_fields);
+    assert!(!report.is_usable());
+  }
+}
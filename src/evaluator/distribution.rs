@@ -0,0 +1,64 @@
+use super::*;
+use rayon::prelude::*;
+
+// the probabilities of reaching each combo count under the policy maximizing `values`,
+// computed one combo count further by each `next`
+pub struct ComboDistribution<'a, T: States> {
+  // `tails[k - 1][state]` is the probability that the combo from the state reaches k
+  pub tails: Vec<Vec<f64>>,
+  states: &'a T,
+  values: &'a [f64]
+}
+
+impl<'a, T: States> ComboDistribution<'a, T> {
+  pub fn new(states: &'a T, values: &'a [f64]) -> Self {
+    Self { tails: vec![], states, values }
+  }
+  // the next state with the best value, the first one in ties
+  pub fn choose(&self, branch: T::Branch) -> Option<usize> {
+    self
+      .states
+      .next_states(branch)
+      .map(|state| self.states.encode(&state).unwrap())
+      .reduce(|best, next| if self.values[next] > self.values[best] { next } else { best })
+  }
+  // the probability that the combo from the state reaches each count up to the cutoff
+  pub fn tail(&self, state: usize) -> Vec<f64> {
+    self.tails.iter().map(|tail| tail[state]).collect()
+  }
+  // the probability that the combo from the state ends at most at each count below the cutoff
+  pub fn cdf(&self, state: usize) -> Vec<f64> {
+    self.tails.iter().map(|tail| 1. - tail[state]).collect()
+  }
+}
+
+impl<'a, T: States> Evaluator for ComboDistribution<'a, T> {
+  type Item<'b>
+    = &'b [f64]
+  where Self: 'b;
+  fn next<'b>(&'b mut self) -> Self::Item<'b> {
+    let last = self.tails.last();
+    let tail = (0..self.states.len())
+      .into_par_iter()
+      .map(|i| {
+        let state = self.states.decode(i).unwrap();
+        let mut total = 0.;
+        let mut total_weight = 0;
+        for branch in self.states.next_pieces(state) {
+          let weight = self.states.weight(&branch);
+          if let Some(next) = self.choose(branch) {
+            total += last.map_or(1., |last| last[next]) * weight as f64;
+          }
+          total_weight += weight;
+        }
+        if total_weight == 0 {
+          0.
+        } else {
+          total / total_weight as f64
+        }
+      })
+      .collect();
+    self.tails.push(tail);
+    self.tails.last().unwrap()
+  }
+}
@@ -1,6 +1,15 @@
 mod value_iteration;
 pub use value_iteration::*;
 mod loop_finder;
+pub use loop_finder::*;
+mod reward;
+pub use reward::*;
+mod distribution;
+pub use distribution::*;
+mod threshold;
+pub use threshold::*;
+mod policy;
+pub use policy::*;
 
 
 use crate::states::*;
@@ -0,0 +1,137 @@
+use super::*;
+use crate::basics::Piece;
+use crate::continuations::Placement;
+use arrayvec::ArrayVec;
+use itertools::Itertools;
+use rayon::prelude::*;
+
+// the original states with the class of each state, the values of the classes and the class
+// chosen by each branch of each class, without the branches of the classes, where the original
+// states are kept for the placements and the sequence, and the classes are 32-bit
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Policy<T: States> {
+  pub original: T,
+  pub mapping: Vec<u32>,
+  pub values: Vec<f64>,
+  // the best target of each branch of the class in the order of the branches of the minimized
+  // states, by their targets, the first one if tied and `None` if the branch has no targets
+  pub choices: Vec<ArrayVec<Option<u32>, 7>>
+}
+
+impl<T: States> Policy<T> {
+  pub fn new(states: ConcreteMappedStates<T>, values: Vec<f64>) -> Self {
+    assert!(states.len() <= u32::MAX as usize, "too many classes for a policy file");
+    let choices = (0..states.len())
+      .into_par_iter()
+      .map(|i| {
+        states
+          .next_pieces(i)
+          .map(|branch| {
+            let best = states
+              .next_states(branch)
+              .reduce(|best, next| if values[next] > values[best] { next } else { best });
+            best.map(|best| best as u32)
+          })
+          .collect()
+      })
+      .collect();
+    let mapping = states.mapping.into_par_iter().map(|class| class as u32).collect();
+    Self { original: states.original, mapping, values, choices }
+  }
+  // the class of a state of the original states
+  pub fn class(&self, state: &T::State) -> usize {
+    self.mapping[self.original.encode(state).unwrap()] as usize
+  }
+  // the classes a branch of the original states leads to, as the minimized states list them
+  fn targets(&self, branch: T::Branch) -> Vec<usize> {
+    self.original.next_states(branch).map(|next| self.class(&next)).sorted().dedup().collect()
+  }
+  // the expected combos from a state of the original states
+  pub fn value(&self, state: &T::State) -> f64 {
+    self.values[self.class(state)] - self.original.extra_moves(state) as f64
+  }
+}
+
+// the state after a move, the piece and its placement as in `next_moves`,
+// and the expected combos after the move counting its placement
+pub type ValuedMove<S> =
+  (<FieldSequenceStates<S> as States>::State, Piece, Option<Placement>, f64);
+
+impl<S: SequenceStates> Policy<FieldSequenceStates<S>> {
+  // the moves of the branch in the order of `next_moves`
+  pub fn moves(
+    &self,
+    branch: <FieldSequenceStates<S> as States>::Branch
+  ) -> Vec<ValuedMove<S>> {
+    self
+      .original
+      .next_moves(branch)
+      .map(|(next, piece, placement)| {
+        (next, piece, placement, self.value(&next) + placement.is_some() as u8 as f64)
+      })
+      .collect()
+  }
+  // the moves of the branches of the same position in different sequences, which only differ
+  // in the sequences after them, with the values averaged one by one weighted by the probability
+  // of each branch
+  pub fn average_moves(
+    &self,
+    branches: &[(<FieldSequenceStates<S> as States>::Branch, f64)]
+  ) -> Vec<ValuedMove<S>> {
+    let moves = branches.iter().map(|&(branch, _)| self.moves(branch)).collect_vec();
+    (0..moves.first().map_or(0, Vec::len))
+      .map(|i| {
+        let (next, piece, placement, _) = moves[0][i];
+        let value = moves.iter().zip(branches).map(|(moves, (_, p))| moves[i].3 * p).sum::<f64>();
+        (next, piece, placement, value)
+      })
+      .collect()
+  }
+  // the first move of the branch into the class chosen by the branch of the minimized state,
+  // `None` if there is no move
+  pub fn choose(
+    &self,
+    state: &<FieldSequenceStates<S> as States>::State,
+    branch: <FieldSequenceStates<S> as States>::Branch
+  ) -> Option<(<FieldSequenceStates<S> as States>::State, Piece, Option<Placement>)> {
+    // the branches of the minimized state are sorted and merged by their targets
+    let branches =
+      self.original.next_pieces(*state).map(|branch| self.targets(branch)).sorted().dedup();
+    let index = branches.collect_vec().binary_search(&self.targets(branch)).ok()?;
+    let choice = self.choices[self.class(state)][index]? as usize;
+    self.original.next_moves(branch).find(|(next, _, _)| self.class(next) == choice)
+  }
+}
+
+// a policy with the sequence model of its states, as saved in the policy files
+#[derive(serde::Serialize, serde::Deserialize)]
+pub enum PolicyFile {
+  Random(Policy<FieldSequenceStates<RandomSequenceStates>>),
+  Bag(Policy<FieldSequenceStates<BagSequenceStates>>),
+  History(Policy<FieldSequenceStates<HistorySequenceStates>>),
+  Automaton(Policy<FieldSequenceStates<AutomatonSequenceStates>>)
+}
+
+impl From<Policy<FieldSequenceStates<RandomSequenceStates>>> for PolicyFile {
+  fn from(policy: Policy<FieldSequenceStates<RandomSequenceStates>>) -> Self {
+    PolicyFile::Random(policy)
+  }
+}
+
+impl From<Policy<FieldSequenceStates<BagSequenceStates>>> for PolicyFile {
+  fn from(policy: Policy<FieldSequenceStates<BagSequenceStates>>) -> Self {
+    PolicyFile::Bag(policy)
+  }
+}
+
+impl From<Policy<FieldSequenceStates<HistorySequenceStates>>> for PolicyFile {
+  fn from(policy: Policy<FieldSequenceStates<HistorySequenceStates>>) -> Self {
+    PolicyFile::History(policy)
+  }
+}
+
+impl From<Policy<FieldSequenceStates<AutomatonSequenceStates>>> for PolicyFile {
+  fn from(policy: Policy<FieldSequenceStates<AutomatonSequenceStates>>) -> Self {
+    PolicyFile::Automaton(policy)
+  }
+}
@@ -0,0 +1,175 @@
+use super::*;
+use crate::basics::Piece;
+use crate::continuations::{Placement, Spin};
+use average::{Estimate, Max};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub trait RewardModel: Sync {
+  // the reward of a placement, where the combo index is the number of clears right before it
+  fn reward(&self, combo: usize, piece: Piece, placement: &Placement) -> f64;
+  // the combo index from which the rewards stop changing
+  fn max_combo(&self) -> usize;
+}
+
+// +1 per placement, the same objective as `ValueIterator` without its extra moves
+pub struct ComboCount;
+
+impl RewardModel for ComboCount {
+  fn reward(&self, _: usize, _: Piece, _: &Placement) -> f64 {
+    1.
+  }
+  fn max_combo(&self) -> usize {
+    0
+  }
+}
+
+// the lines sent by a placement, as the combo bonus plus the bonus for the cleared lines
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AttackTable {
+  // by the combo index, repeating the last entry after the end
+  pub combo: Vec<f64>,
+  // by the number of cleared lines, for each kind of placement, up to the most lines it can clear
+  pub lines: Vec<f64>,
+  pub tspin: Vec<f64>,
+  pub tspin_mini: Vec<f64>,
+  pub allspin: Vec<f64>
+}
+
+impl AttackTable {
+  pub fn guideline() -> Self {
+    Self::parse(include_str!("../../attack_tables/guideline.json")).unwrap()
+  }
+  pub fn load(path: &std::path::Path) -> Result<Self, String> {
+    Self::parse(&std::fs::read_to_string(path).map_err(|err| err.to_string())?)
+  }
+  pub fn parse(description: &str) -> Result<Self, String> {
+    let table: Self = serde_json::from_str(description).map_err(|err| err.to_string())?;
+    if table.combo.is_empty() {
+      return Err("empty combo table".into());
+    }
+    let lengths = [
+      ("lines", &table.lines, 5),
+      ("tspin", &table.tspin, 4),
+      ("tspin_mini", &table.tspin_mini, 3),
+      ("allspin", &table.allspin, 5)
+    ];
+    for (name, lines, needed) in lengths {
+      if lines.len() < needed {
+        let len = lines.len();
+        return Err(format!("only {} entries in the {} table, fewer than {}", len, name, needed));
+      }
+    }
+    Ok(table)
+  }
+}
+
+impl RewardModel for AttackTable {
+  fn reward(&self, combo: usize, _: Piece, placement: &Placement) -> f64 {
+    let lines = match placement.spin {
+      Spin::None => &self.lines,
+      Spin::AllSpin => &self.allspin,
+      Spin::TSpinMini => &self.tspin_mini,
+      Spin::TSpin => &self.tspin
+    };
+    // a T-spin clearing 4 lines or a T-spin mini clearing 3 cannot happen, and sends nothing
+    let lines = lines.get(placement.lines as usize).copied().unwrap_or(0.);
+    self.combo[combo.min(self.max_combo())] + lines
+  }
+  fn max_combo(&self) -> usize {
+    self.combo.len() - 1
+  }
+}
+
+// value iteration maximizing the expected total reward until the combo breaks,
+// over the raw states as the minimized ones forget the placements
+pub struct RewardIterator<'a, T: MoveStates, R: RewardModel> {
+  // the values of all states for each combo index up to `max_combo`
+  pub values: Vec<f64>,
+  states: &'a T,
+  reward: &'a R
+}
+
+impl<'a, T: MoveStates, R: RewardModel> RewardIterator<'a, T, R> {
+  pub fn new(states: &'a T, reward: &'a R) -> Self {
+    let mut values = vec![0.0; states.len() * (reward.max_combo() + 1)];
+    values.shrink_to_fit();
+    Self { values, states, reward }
+  }
+  pub fn value(&self, combo: usize, state: usize) -> f64 {
+    self.values[combo.min(self.reward.max_combo()) * self.states.len() + state]
+  }
+}
+
+impl<'a, T: MoveStates, R: RewardModel> Evaluator for RewardIterator<'a, T, R> {
+  type Item<'b>
+    = (&'b [f64], f64)
+  where Self: 'b;
+  fn next<'b>(&'b mut self) -> Self::Item<'b> {
+    let len = self.states.len();
+    let (new_values, diffs): (Vec<_>, Vec<_>) = (0..self.values.len())
+      .into_par_iter()
+      .map(|j| {
+        let (combo, i) = (j / len, j % len);
+        let state = self.states.decode(i).unwrap();
+        let mut total = 0.;
+        let mut total_weight = 0;
+        for next in self.states.next_pieces(state) {
+          let weight = self.states.weight(&next);
+          let mut best = Max::from_value(0.);
+          for (next_state, piece, placement) in self.states.next_moves(next) {
+            let next_state = self.states.encode(&next_state).unwrap();
+            // a move placing no piece keeps the combo
+            best.add(match placement {
+              Some(placement) => {
+                let next_combo = (combo + 1).min(self.reward.max_combo());
+                let next_value = self.values[next_combo * len + next_state];
+                self.reward.reward(combo, piece, &placement) + next_value
+              }
+              None => self.values[combo * len + next_state]
+            });
+          }
+          total += best.max() * weight as f64;
+          total_weight += weight;
+        }
+        let new_value = if total_weight == 0 { 0. } else { total / total_weight as f64 };
+        (new_value, (new_value - self.values[j]).abs())
+      })
+      .unzip();
+    let diff = diffs.into_iter().fold(0., f64::max);
+    self.values = new_values;
+    self.values.shrink_to_fit();
+    (&self.values, diff)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn placement(lines: u8, spin: Spin) -> Placement {
+    Placement { orientation: 0, x: 0, y: 0, softdrop: false, lines, spin }
+  }
+
+  #[test]
+  fn guideline() {
+    let table = AttackTable::guideline();
+    let reward = |combo, piece, lines, spin| table.reward(combo, piece, &placement(lines, spin));
+    assert_eq!(reward(0, Piece::I, 1, Spin::None), 0.);
+    assert_eq!(reward(1, Piece::I, 1, Spin::None), 1.);
+    assert_eq!(reward(3, Piece::I, 4, Spin::None), 6.);
+    assert_eq!(reward(0, Piece::T, 2, Spin::TSpin), 4.);
+    assert_eq!(reward(4, Piece::T, 1, Spin::TSpinMini), 2.);
+    assert_eq!(reward(2, Piece::J, 3, Spin::AllSpin), 3.);
+    assert_eq!(reward(100, Piece::O, 2, Spin::None), 6.);
+    assert_eq!(reward(0, Piece::T, 3, Spin::TSpinMini), 0.);
+  }
+
+  #[test]
+  fn short_tables() {
+    let table = r#"{"combo": [0], "lines": [0, 0, 1, 2, 4], "tspin": [0, 2, 4],
+      "tspin_mini": [0, 0, 1], "allspin": [0, 0, 1, 2, 4]}"#;
+    assert!(AttackTable::parse(table).unwrap_err().contains("tspin"));
+    assert!(AttackTable::parse(&table.replace("[0, 2, 4]", "[0, 2, 4, 6]")).is_ok());
+  }
+}
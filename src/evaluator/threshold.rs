@@ -0,0 +1,56 @@
+use super::*;
+use rayon::prelude::*;
+
+// the best probabilities of reaching a number of combos, one more combo by each `next`,
+// where the choices may depend on the number of combos still to go
+pub struct ThresholdIterator<'a, T: States> {
+  // `layers[r][state]` is the best probability to reach r more combos from the state
+  pub layers: Vec<Vec<f64>>,
+  states: &'a T
+}
+
+impl<'a, T: States> ThresholdIterator<'a, T> {
+  pub fn new(states: &'a T) -> Self {
+    Self { layers: vec![vec![1.; states.len()]], states }
+  }
+  // the next state most likely to reach the remaining combos after this one, the first one in ties
+  pub fn choose(&self, branch: T::Branch, remaining: usize) -> Option<usize> {
+    let layer = &self.layers[remaining - 1];
+    self
+      .states
+      .next_states(branch)
+      .map(|state| self.states.encode(&state).unwrap())
+      .reduce(|best, next| if layer[next] > layer[best] { next } else { best })
+  }
+}
+
+impl<'a, T: States> Evaluator for ThresholdIterator<'a, T> {
+  type Item<'b>
+    = &'b [f64]
+  where Self: 'b;
+  fn next<'b>(&'b mut self) -> Self::Item<'b> {
+    let last = self.layers.last().unwrap();
+    let layer = (0..self.states.len())
+      .into_par_iter()
+      .map(|i| {
+        let state = self.states.decode(i).unwrap();
+        let mut total = 0.;
+        let mut total_weight = 0;
+        for branch in self.states.next_pieces(state) {
+          let weight = self.states.weight(&branch);
+          if let Some(next) = self.choose(branch, self.layers.len()) {
+            total += last[next] * weight as f64;
+          }
+          total_weight += weight;
+        }
+        if total_weight == 0 {
+          0.
+        } else {
+          total / total_weight as f64
+        }
+      })
+      .collect();
+    self.layers.push(layer);
+    self.layers.last().unwrap()
+  }
+}
@@ -23,26 +23,29 @@ impl<'a, T: States> Evaluator for ValueIterator<'a, T> {
       .map(|j| {
         let mut values = vec![];
         let mut counter_added = 0.;
+        let mut total_weight = 0.;
         let state = self.states.decode(j).unwrap();
         for next in self.states.next_pieces(state) {
+          let weight = self.states.weight(&next) as f64;
           let mut this_value = Max::from_value(0.);
           let mut added = false;
           for next_state in self.states.next_states(next) {
             this_value.add(self.values[self.states.encode(&next_state).unwrap()]);
             added = true;
           }
-          values.push(this_value.max());
+          values.push((this_value.max(), weight));
+          total_weight += weight;
           if added {
-            counter_added += 1.;
+            counter_added += weight;
           }
         }
         values.sort_by(|a, b| a.partial_cmp(b).unwrap());
         let new_value = if values.is_empty() {
           -0.0
-        } else if values.iter().all(|&v| v == values[0]) {
-          values[0] + (counter_added > 0.) as u8 as f64
+        } else if values.iter().all(|&(v, _)| v == values[0].0) {
+          values[0].0 + counter_added / total_weight
         } else {
-          (values.iter().sum::<f64>() + counter_added) / values.len() as f64
+          (values.iter().map(|&(v, weight)| v * weight).sum::<f64>() + counter_added) / total_weight
         };
         let old_value = self.values[j];
         let diff = (new_value - old_value).abs();
@@ -96,3 +99,48 @@ impl ParallelExtend<f64> for MyMax {
     );
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // the next states of each branch of each state, with the weight of the branch
+  struct Branches(Vec<Vec<(Vec<usize>, u64)>>);
+
+  impl HasLength for Branches {
+    fn len(&self) -> usize {
+      self.0.len()
+    }
+  }
+
+  impl States for Branches {
+    type State = usize;
+    type Branch = (Vec<usize>, u64);
+    fn decode(&self, index: usize) -> Option<Self::State> {
+      Some(index)
+    }
+    fn encode(&self, state: &Self::State) -> Option<usize> {
+      Some(*state)
+    }
+    fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Branch> {
+      self.0[state].clone().into_iter()
+    }
+    fn next_states(&self, (states, _): Self::Branch) -> impl Iterator<Item=Self::State> {
+      states.into_iter()
+    }
+    fn weight(&self, (_, weight): &Self::Branch) -> u64 {
+      *weight
+    }
+  }
+
+  #[test]
+  fn tied_branches() {
+    // the combo goes on from the first state with the branch of weight 3 into the last state,
+    // and breaks with the other one, where both branches are worth 0 afterwards
+    let states = Branches(vec![vec![(vec![1], 3), (vec![], 1)], vec![]]);
+    let mut evaluator = ValueIterator::new(&states);
+    while evaluator.next().1 > 0. {}
+    // the expected combo is 3/4, not 1 for going on with any branch
+    assert_eq!(evaluator.values, [0.75, 0.]);
+  }
+}
@@ -1,8 +1,10 @@
 pub mod basics;
+pub mod continuations;
 pub mod evaluator;
 pub mod minimizer;
 pub mod printer;
 pub mod states;
 pub mod prover;
 pub mod pruner;
+pub mod rotation;
 
@@ -1,12 +1,13 @@
 mod dashmap;
-
+pub use self::dashmap::*;
 mod recorder;
-
+pub use recorder::*;
 mod raw;
-
+pub use raw::*;
 mod parallel;
 pub use parallel::*;
 mod conservative;
+pub use conservative::*;
 
 
 use crate::states::*;
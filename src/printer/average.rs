@@ -0,0 +1,14 @@
+use crate::printer::*;
+
+// the value averaged over every state
+pub struct AveragePrinter;
+
+impl<T: States> Printer<T> for AveragePrinter {
+  fn print(field2state: &[usize], values: &[f64], num2state: &T) {
+    let total = (0..num2state.len())
+      .map(|i| values[field2state[i]] - num2state.extra_moves(&num2state.decode(i).unwrap()) as f64)
+      .sum::<f64>();
+    println!("states: {}", num2state.len());
+    println!("average: {:.6}", total / num2state.len() as f64);
+  }
+}
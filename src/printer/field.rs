@@ -0,0 +1,60 @@
+use crate::printer::*;
+use crate::states::{FieldSequenceStates, HasLength, SequenceStates};
+use itertools::Itertools;
+
+// the value of every field averaged over the holds and the sequences, best first
+pub struct FieldPrinter;
+
+// the value of every field with every hold averaged over the sequences, best first
+pub struct FieldHoldPrinter;
+
+fn print_fields<S: SequenceStates>(
+  field2state: &[usize],
+  values: &[f64],
+  num2state: &FieldSequenceStates<S>,
+  by_hold: bool
+) {
+  let fields = num2state.fields();
+  let holds = if by_hold { num2state.holds() } else { 1 };
+  let mut totals = vec![(0., 0); fields.len() * holds];
+  for i in 0..num2state.len() {
+    let state = num2state.decode(i).unwrap();
+    let (field, hold, _) = state;
+    let total = &mut totals[if holds > 1 { hold * fields.len() + field } else { field }];
+    total.0 += values[field2state[i]] - num2state.extra_moves(&state) as f64;
+    total.1 += 1;
+  }
+  let ranked = totals
+    .into_iter()
+    .enumerate()
+    .filter(|&(_, (_, count))| count > 0)
+    .map(|(i, (total, count))| (i, total / count as f64))
+    .sorted_by(|a, b| b.1.total_cmp(&a.1));
+  for (i, value) in ranked {
+    let (hold, field) = (i / fields.len(), i % fields.len());
+    if holds > 1 {
+      match num2state.held(hold) {
+        None => println!("{:.6} hold empty", value),
+        Some(piece) if num2state.pulled().is_some_and(|pulled| hold >= pulled) => {
+          println!("{:.6} hold {:?} pulled", value, piece)
+        }
+        Some(piece) => println!("{:.6} hold {:?}", value, piece)
+      }
+    } else {
+      println!("{:.6}", value);
+    }
+    println!("{}", fields[field]);
+  }
+}
+
+impl<S: SequenceStates> Printer<FieldSequenceStates<S>> for FieldPrinter {
+  fn print(field2state: &[usize], values: &[f64], num2state: &FieldSequenceStates<S>) {
+    print_fields(field2state, values, num2state, false);
+  }
+}
+
+impl<S: SequenceStates> Printer<FieldSequenceStates<S>> for FieldHoldPrinter {
+  fn print(field2state: &[usize], values: &[f64], num2state: &FieldSequenceStates<S>) {
+    print_fields(field2state, values, num2state, true);
+  }
+}
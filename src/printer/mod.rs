@@ -1,11 +1,14 @@
-use crate::states::States;
-
-
-
+mod average;
+pub use average::*;
+mod raw;
+pub use raw::*;
+mod field;
+pub use field::*;
 
+use crate::states::States;
 
-pub trait Printer {
-  fn print<T: States>(field2state: &[usize], values: &[f64], num2state: &T);
+pub trait Printer<T: States> {
+  fn print(field2state: &[usize], values: &[f64], num2state: &T);
 }
 
 // pub struct MarkovAverage();
@@ -0,0 +1,13 @@
+use crate::printer::*;
+
+// the value of every state, one per line in the order of the states
+pub struct RawPrinter;
+
+impl<T: States> Printer<T> for RawPrinter {
+  fn print(field2state: &[usize], values: &[f64], num2state: &T) {
+    for i in 0..num2state.len() {
+      let extra = num2state.extra_moves(&num2state.decode(i).unwrap()) as f64;
+      println!("{} {}", i, values[field2state[i]] - extra);
+    }
+  }
+}
@@ -17,12 +17,17 @@ impl Branch {
   }
 }
 
+// the branches with their weights
 #[derive(Clone)]
-struct Next(ArrayVec<usize, 7>);
+pub(crate) struct Next(pub(crate) ArrayVec<(usize, u64), 7>);
 impl Next {
-  fn is_geq(&self, other: &Self, branch_geq: impl Fn(usize, usize) -> bool) -> bool {
+  // whether the weights of `other` can be moved to not worse branches in `self`,
+  // after both are scaled to the same total
+  pub(crate) fn is_geq(&self, other: &Self, branch_geq: impl Fn(usize, usize) -> bool) -> bool {
     let left = self.0.clone();
     let right = other.0.clone();
+    let left_total = left.iter().map(|&(_, weight)| weight as usize).sum::<usize>();
+    let right_total = right.iter().map(|&(_, weight)| weight as usize).sum::<usize>();
     let mut graph_builder = VecGraphBuilder::<usize>::with_capacities(
       2 + left.len() + right.len(),
       (1 + left.len()) * (1 + right.len()) - 1
@@ -40,8 +45,8 @@ impl Next {
       let edge = graph_builder.add_edge(right_branch, right_node);
       assert!(edge.index() >= left.len() && edge.index() < left.len() + right.len());
     }
-    for (&left_id, &left_branch) in left.iter().zip(left_branches.iter()) {
-      for (&right_id, &right_branch) in right.iter().zip(right_branches.iter()) {
+    for (&(left_id, _), &left_branch) in left.iter().zip(left_branches.iter()) {
+      for (&(right_id, _), &right_branch) in right.iter().zip(right_branches.iter()) {
         if branch_geq(left_id, right_id) {
           graph_builder.add_edge(left_branch, right_branch);
         }
@@ -51,14 +56,14 @@ impl Next {
     let mut flow = EdmondsKarp::new(&graph);
     flow.solve(left_node, right_node, |e| {
       if e.index() < left.len() {
-        right.len()
+        left[e.index()].1 as usize * right_total
       } else if e.index() < left.len() + right.len() {
-        left.len()
+        right[e.index() - left.len()].1 as usize * left_total
       } else {
         usize::MAX
       }
     });
-    flow.value() == left.len() * right.len()
+    flow.value() == left_total * right_total
   }
 }
 
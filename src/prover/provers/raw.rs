@@ -14,7 +14,7 @@ struct WorkingRawProver<U: Poset, T: States> {
   states: T
 }
 impl<U: Poset, T: States> WorkingRawProver<U, T> {
-  fn static_get_next(poset: &U, mapping: &[usize], states: &T, state: usize) -> ArrayVec<Branch, 7> {
+  fn static_get_next(poset: &U, mapping: &[usize], states: &T, state: usize) -> ArrayVec<(Branch, u64), 7> {
     states.true_get_next(state, |v| {
       let mut result = vec![];
       for i in v.into_iter().map(|i| mapping[i]) {
@@ -27,12 +27,13 @@ impl<U: Poset, T: States> WorkingRawProver<U, T> {
       Branch(result)
     })
   }
-  fn get_next(&self, state: usize) -> ArrayVec<Branch, 7> {
+  fn get_next(&self, state: usize) -> ArrayVec<(Branch, u64), 7> {
     Self::static_get_next(&self.poset, &self.mapping, &self.states, state)
   }
-  fn split_nodes(&self, nexts: Vec<ArrayVec<Branch, 7>>) -> Vec<Vec<bool>> {
+  fn split_nodes(&self, nexts: Vec<ArrayVec<(Branch, u64), 7>>) -> Vec<Vec<bool>> {
     let branches = nexts.iter()
       .flatten()
+      .map(|(branch, _)| branch)
       .collect::<HashSet<_>>()
       .into_iter()
       .cloned()
@@ -44,7 +45,7 @@ impl<U: Poset, T: States> WorkingRawProver<U, T> {
         .map(|(i, next)| (next.clone(), i))
         .collect::<HashMap<_, _>>();
       nexts.into_par_iter()
-        .map(|next| Next(next.into_iter().map(|next| branch_to_id[&next]).collect::<ArrayVec<_, 7>>()))
+        .map(|next| Next(next.into_iter().map(|(next, weight)| (branch_to_id[&next], weight)).collect::<ArrayVec<_, 7>>()))
         .collect::<Vec<_>>()
     };
     let branch_geqs = branches.iter().flat_map(|left| {
@@ -65,7 +66,7 @@ impl<U: Poset, T: States> WorkingProver<T> for WorkingRawProver<U, T> {
     let prev_nexts = self.seeds.iter()
       .map(|&i| self.get_next(i))
       .collect_vec();
-    let mut prev_id_to_next = vec![Vec::<(ArrayVec<Branch, 7>, usize)>::new(); self.poset.len()];
+    let mut prev_id_to_next = vec![Vec::<(ArrayVec<(Branch, u64), 7>, usize)>::new(); self.poset.len()];
     let new_mapping = self.mapping.iter()
       .enumerate()
       .map(|(state, &prev_id)| {
@@ -133,11 +134,11 @@ impl<U: Poset, T: States> WorkingProver<T> for WorkingRawProver<U, T> {
     let found = self.poset.verify_edges(|poset, left, right| {
       let left = Self::static_get_next(poset, &self.mapping, &self.states, self.seeds[left]);
       let right = Self::static_get_next(poset, &self.mapping, &self.states, self.seeds[right]);
-      let left_next = Next((0..left.len()).collect());
-      let right_next = Next((0..right.len()).map(|i| i + left.len()).collect());
+      let left_next = Next(left.iter().enumerate().map(|(i, &(_, weight))| (i, weight)).collect());
+      let right_next = Next(right.iter().enumerate().map(|(i, &(_, weight))| (i + left.len(), weight)).collect());
       
       left_next.is_geq(&right_next, |left_id, right_id| {
-        left[left_id].is_geq(&right[right_id - left.len()], |l, r| poset.has_relation(l, r))
+        left[left_id].0.is_geq(&right[right_id - left.len()].0, |l, r| poset.has_relation(l, r))
       })
     });
     eprint!("=> ");
@@ -145,13 +146,19 @@ impl<U: Poset, T: States> WorkingProver<T> for WorkingRawProver<U, T> {
     found
   }
   fn get_concrete(self) -> ConcreteMappedStates<T> {
+    let mut weights = Vec::with_capacity(self.seeds.len());
     let nexts = self.seeds.iter()
-      .map(|&i| self.get_next(i).into_iter().map(|s| s.0))
+      .map(|&i| {
+        let next = self.get_next(i);
+        weights.push(next.iter().map(|&(_, weight)| weight).collect());
+        next.into_iter().map(|(s, _)| s.0)
+      })
       .collect();
     ConcreteMappedStates {
       original: self.states,
       mapping: self.mapping,
-      nexts
+      nexts,
+      weights
     }
   }
 }
@@ -1,8 +1,7 @@
+use crate::prover::Next;
 use crate::pruner::*;
 use arrayvec::ArrayVec;
-use hopcroft_karp::matching;
 use itertools::{iproduct, Itertools};
-use num_integer::Integer;
 use rayon::prelude::*;
 
 pub struct PlainPruner;
@@ -81,16 +80,15 @@ fn find_smaller<T: States>(states: &T, u1: usize, u2: usize) -> Option<(usize, u
   if u1 == u2 {
     return Some((u1, u2));
   }
-  let get_nexts = |u: usize| -> ArrayVec<Vec<usize>, 7> {
+  let get_nexts = |u: usize| -> ArrayVec<(Vec<usize>, u64), 7> {
     let s = states.decode(u).unwrap();
-    let mut nexts: ArrayVec<Vec<usize>, 7> = states
+    states
       .next_pieces(s)
       .map(|piece| {
-        states.next_states(piece).map(|state| states.encode(&state).unwrap()).collect()
+        let weight = states.weight(&piece);
+        (states.next_states(piece).map(|state| states.encode(&state).unwrap()).collect(), weight)
       })
-      .collect();
-    nexts.sort_unstable_by_key(|v| v.len());
-    nexts
+      .collect()
   };
   let nexts1 = get_nexts(u1);
   let nexts2 = get_nexts(u2);
@@ -100,31 +98,18 @@ fn find_smaller<T: States>(states: &T, u1: usize, u2: usize) -> Option<(usize, u
     return Some((u1, u2));
   }
 
-  let get_multiplier = |a: usize, b: usize| (b / a.gcd(&b), a / a.gcd(&b));
-  let (multiplier1, multiplier2) = get_multiplier(nexts1.len(), nexts2.len());
-  let bases = iproduct!(
-    (0..multiplier1).map(|i| i * nexts1.len()),
-    (multiplier2..multiplier2 * 2).map(|i| i * nexts2.len())
-  );
-  let edging_size = nexts1.len() * multiplier1;
-  let edge_1_2 = iproduct!(nexts1.iter().enumerate(), nexts2.iter().enumerate())
-    .filter_map(|((i1, next1), (i2, next2))| {
-      (next1.len() <= next2.len() && next1.iter().all(|i| next2.contains(i)))
-        .then_some(bases.clone().map(move |(b1, b2)| (b1 + i1, b2 + i2)))
-    })
-    .flatten()
-    .collect_vec();
-  if matching(&edge_1_2).len() >= edging_size {
+  // a branch with all the choices of another is not worse
+  let contains = |large: &Vec<usize>, small: &Vec<usize>| {
+    small.len() <= large.len() && small.iter().all(|i| large.contains(i))
+  };
+  let to_next = |nexts: &ArrayVec<(Vec<usize>, u64), 7>| {
+    Next(nexts.iter().enumerate().map(|(i, &(_, weight))| (i, weight)).collect())
+  };
+  let (next1, next2) = (to_next(&nexts1), to_next(&nexts2));
+  if next2.is_geq(&next1, |i2, i1| contains(&nexts2[i2].0, &nexts1[i1].0)) {
     return Some((u2, u1));
   }
-  let edge_2_1 = iproduct!(nexts1.iter().enumerate(), nexts2.iter().enumerate())
-    .filter_map(|((i1, next1), (i2, next2))| {
-      (next2.len() <= next1.len() && next2.iter().all(|i| next1.contains(i)))
-        .then_some(bases.clone().map(move |(b1, b2)| (b1 + i1, b2 + i2)))
-    })
-    .flatten()
-    .collect_vec();
-  if matching(&edge_2_1).len() >= edging_size {
+  if next1.is_geq(&next2, |i1, i2| contains(&nexts1[i1].0, &nexts2[i2].0)) {
     return Some((u1, u2));
   }
   None
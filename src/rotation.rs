@@ -0,0 +1,79 @@
+use crate::basics::{Piece, PIECES};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// offsets are (x to the right, y upwards), tried in order until the rotated piece fits
+pub type Kicks = [Vec<(i32, i32)>; 4];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PieceRotation {
+  // shapes inside the bounding box, top row first, with `X` for the minos,
+  // in the order of north, east, south and west orientations
+  pub shapes: [Vec<String>; 4],
+  #[serde(default)]
+  pub spawn: usize,
+  // kicks when rotating out of each orientation
+  pub clockwise: Kicks,
+  pub counterclockwise: Kicks,
+  #[serde(default)]
+  pub half: Option<Kicks>
+}
+
+impl PieceRotation {
+  // the bounding box is the same for every orientation
+  pub fn size(&self) -> (i32, i32) {
+    (self.shapes[0][0].len() as i32, self.shapes[0].len() as i32)
+  }
+  pub fn cells(&self, orientation: usize) -> Vec<(i32, i32)> {
+    self.shapes[orientation]
+      .iter()
+      .rev()
+      .enumerate()
+      .flat_map(|(y, row)| {
+        row.chars().enumerate().filter(|&(_, c)| c == 'X').map(move |(x, _)| (x as i32, y as i32))
+      })
+      .collect()
+  }
+  // the target orientations and their kicks when rotating out of `from`
+  pub fn rotations(&self, from: usize) -> impl Iterator<Item=(usize, &[(i32, i32)])> {
+    [(1, Some(&self.clockwise)), (3, Some(&self.counterclockwise)), (2, self.half.as_ref())]
+      .into_iter()
+      .filter_map(move |(turn, kicks)| kicks.map(|kicks| ((from + turn) % 4, &kicks[from][..])))
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RotationSystem(pub HashMap<Piece, PieceRotation>);
+
+impl RotationSystem {
+  pub fn srs() -> Self {
+    Self::parse(include_str!("../rotation_systems/srs.json")).unwrap()
+  }
+  pub fn load(path: &std::path::Path) -> Result<Self, String> {
+    Self::parse(&std::fs::read_to_string(path).map_err(|err| err.to_string())?)
+  }
+  pub fn parse(description: &str) -> Result<Self, String> {
+    let system: Self = serde_json::from_str(description).map_err(|err| err.to_string())?;
+    for piece in PIECES.iter() {
+      let rotation = system.0.get(piece).ok_or_else(|| format!("missing piece {:?}", piece))?;
+      let (width, height) = rotation.size();
+      if rotation.spawn >= 4 {
+        return Err(format!("invalid spawn orientation of {:?}", piece));
+      }
+      for shape in rotation.shapes.iter() {
+        if shape.len() as i32 != height || shape.iter().any(|row| row.len() as i32 != width) {
+          return Err(format!("shapes of {:?} have different bounding boxes", piece));
+        }
+      }
+      for orientation in 0..4 {
+        if rotation.cells(orientation).len() != 4 {
+          return Err(format!("{:?} must have 4 minos", piece));
+        }
+      }
+    }
+    Ok(system)
+  }
+  pub fn piece(&self, piece: &Piece) -> &PieceRotation {
+    &self.0[piece]
+  }
+}
@@ -1,16 +1,100 @@
 use crate::states::*;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
 
 pub trait SequenceStates: HasLength+std::marker::Sync {
   type State: Copy;
-  type Proxy: StateWithPiece<Self::State>;
+  type Proxy: StateWithPiece<Self::State>+Copy;
   fn new(preview: usize, base_len: usize) -> Self;
   fn decode(&self, index: usize) -> Option<Self::State>;
   fn encode(&self, state: &Self::State) -> Option<usize>;
   fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Proxy>;
+  // the index of every state after replacing each piece `i` with `permutation[i]`,
+  // or `None` if the sequence is not symmetric under the permutation
+  fn permute(&self, _permutation: &[usize]) -> Option<Vec<usize>> {
+    None
+  }
+  fn preview(&self) -> usize;
+  // the number of each piece in a bag, if the pieces are drawn from bags
+  fn bag(&self) -> Option<&[usize]> {
+    None
+  }
+  // the pieces in the preview of a state, which it draws next whichever way it goes
+  fn queue(&self, index: usize) -> Vec<usize> {
+    let mut state = self.decode(index).unwrap();
+    (0..self.preview())
+      .map(|_| {
+        let next = self.next_pieces(state).next().unwrap();
+        state = next.gen_state();
+        next.gen_piece()
+      })
+      .collect()
+  }
+  // the states with the preview starting with `queue`, and with the number of each piece left
+  // in the bag after the preview if it is known, which only the sequences with bags take
+  fn find(&self, queue: &[usize], left: Option<&[usize]>) -> Vec<usize> {
+    assert!(left.is_none(), "the sequence has no bags");
+    (0..self.len()).filter(|&i| self.queue(i).starts_with(queue)).collect()
+  }
+  // the probability of each state in the long run
+  fn stationary(&self) -> Vec<f64> {
+    // the state after each branch with its probability
+    let transitions = (0..self.len())
+      .map(|i| {
+        let branches = self.next_pieces(self.decode(i).unwrap()).collect_vec();
+        let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>() as f64;
+        branches
+          .iter()
+          .map(|branch| {
+            let after = self.encode(&branch.gen_state()).unwrap();
+            (after, branch.gen_weight() as f64 / total)
+          })
+          .collect_vec()
+      })
+      .collect_vec();
+    let mut probabilities = vec![1. / self.len() as f64; self.len()];
+    loop {
+      // half of each state stays, or the positions in a bag would cycle forever
+      let mut next = probabilities.iter().map(|probability| probability / 2.).collect_vec();
+      for (branches, &probability) in transitions.iter().zip(&probabilities) {
+        for &(after, weight) in branches {
+          next[after] += probability / 2. * weight;
+        }
+      }
+      let diff =
+        next.iter().zip(&probabilities).map(|(next, old)| (next - old).abs()).fold(0., f64::max);
+      probabilities = next;
+      if diff < 1e-12 {
+        return probabilities;
+      }
+    }
+  }
+  // the probability of drawing `pieces` next from the state
+  fn draw_probability(&self, index: usize, pieces: &[usize]) -> f64 {
+    let mut states = HashMap::from([(index, 1.)]);
+    for &piece in pieces {
+      let mut next_states = HashMap::new();
+      for (index, probability) in states {
+        let branches = self.next_pieces(self.decode(index).unwrap()).collect_vec();
+        let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>() as f64;
+        for branch in branches.iter().filter(|branch| branch.gen_piece() == piece) {
+          let after = self.encode(&branch.gen_state()).unwrap();
+          *next_states.entry(after).or_insert(0.) +=
+            probability * branch.gen_weight() as f64 / total;
+        }
+      }
+      states = next_states;
+    }
+    states.values().sum()
+  }
 }
 pub trait StateWithPiece<T> {
   fn gen_state(&self) -> T;
   fn gen_piece(&self) -> usize;
+  // the probability of the piece relative to the other pieces
+  fn gen_weight(&self) -> u64 {
+    1
+  }
 }
 impl<T: Clone> StateWithPiece<T> for (T, usize) {
   fn gen_state(&self) -> T {
@@ -20,13 +104,45 @@ impl<T: Clone> StateWithPiece<T> for (T, usize) {
     self.1
   }
 }
+impl<T: Clone> StateWithPiece<T> for (T, usize, u64) {
+  fn gen_state(&self) -> T {
+    self.0.clone()
+  }
+  fn gen_piece(&self) -> usize {
+    self.1
+  }
+  fn gen_weight(&self) -> u64 {
+    self.2
+  }
+}
+
+// what happens when a piece is held into the empty hold, where ending the turn instead is the
+// same as pulling, as the next piece cannot be held either
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FirstHold {
+  // the next piece comes out right away and has to be placed without holding
+  Pull
+}
+
+impl std::str::FromStr for FirstHold {
+  type Err = String;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "pull" => Ok(FirstHold::Pull),
+      _ => Err(format!("invalid first hold {:?}", s))
+    }
+  }
+}
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct FieldSequenceStates<S: SequenceStates> {
   fields: Vec<Field>,
-  continuations: Continuation,
+  continuations: Continuation<(usize, Placement)>,
   base: Vec<Piece>,
   hold: bool,
+  // the hold after every piece in `base` is the empty hold if set,
+  // followed by the pulled pieces for `FirstHold::Pull`
+  first_hold: Option<FirstHold>,
   sequence: S
 }
 impl<S: SequenceStates> States for FieldSequenceStates<S> {
@@ -46,20 +162,44 @@ impl<S: SequenceStates> States for FieldSequenceStates<S> {
   fn next_pieces(&self, (field, hold, sequence): Self::State) -> impl Iterator<Item=Self::Branch> {
     self.sequence.next_pieces(sequence).map(move |p| (field, hold, p))
   }
-  fn next_states(&self, (field, hold, piece): Self::Branch) -> impl Iterator<Item=Self::State> {
-    let indices = &self.continuations.cont_index[field];
+  fn next_states(&self, branch: Self::Branch) -> impl Iterator<Item=Self::State> {
+    self.next_moves(branch).map(|(state, _, _)| state)
+  }
+  fn weight(&self, (_, _, piece): &Self::Branch) -> u64 {
+    piece.gen_weight()
+  }
+  // holding into the empty hold places no piece, and it happens once in every game from it
+  fn extra_moves(&self, &(_, hold, _): &Self::State) -> usize {
+    (Some(hold) == self.empty_hold()) as usize
+  }
+}
+impl<S: SequenceStates> MoveStates for FieldSequenceStates<S> {
+  // the piece is the hold instead of the current piece if they are swapped,
+  // and the current piece with no placement if it is held into the empty hold
+  fn next_moves(
+    &self,
+    (field, hold, piece): Self::Branch
+  ) -> impl Iterator<Item=(Self::State, Piece, Option<Placement>)> {
     let sequence = piece.gen_state();
     let current = piece.gen_piece();
-    let (left, right) = indices[self.base[current] as usize];
-    self.continuations.continuations[left..right]
-      .iter()
-      .map(move |&field| (field, hold, sequence))
-      .chain({
-        let (left, right) = if self.hold { indices[self.base[hold] as usize] } else { (0, 0) };
-        self.continuations.continuations[left..right]
-          .iter()
-          .map(move |&field| (field, current, sequence))
-      })
+    // the pulled piece has to be placed, and the held one stays in the hold
+    let kept = self.pulled().filter(|&pulled| hold >= pulled).map_or(hold, |pulled| hold - pulled);
+    let placed = self.field_moves(field, self.base[current]).iter().map(move |&(field, placement)| {
+      ((field, kept, sequence), self.base[current], Some(placement))
+    });
+    let swapped = if self.hold && hold < self.base.len() {
+      self.field_moves(field, self.base[hold])
+    } else {
+      &[]
+    };
+    let swapped = swapped.iter().map(move |&(field, placement)| {
+      ((field, current, sequence), self.base[hold], Some(placement))
+    });
+    let held = (Some(hold) == self.empty_hold()).then(|| {
+      let hold = self.pulled().map_or(current, |pulled| pulled + current);
+      ((field, hold, sequence), self.base[current], None)
+    });
+    placed.chain(swapped).chain(held)
   }
 }
 impl<S: SequenceStates> HasLength for FieldSequenceStates<S> {
@@ -68,30 +208,212 @@ impl<S: SequenceStates> HasLength for FieldSequenceStates<S> {
   }
 }
 impl<'b, S: SequenceStates> Creatable<'b> for FieldSequenceStates<S> {
-  fn new(
-    continuations: &'b HashMap<Field, HashMap<Piece, Vec<Field>>>,
-    preview: usize,
-    hold: bool
-  ) -> Self {
+  fn new(continuations: &'b Continuations, preview: usize, hold: bool) -> Self {
+    Self::with_sequence(continuations, hold, S::new(preview, PIECES.len()))
+  }
+}
+impl<S: SequenceStates> FieldSequenceStates<S> {
+  // the sequence is over the pieces in the order of `PIECES`
+  pub fn with_sequence(continuations: &Continuations, hold: bool, sequence: S) -> Self {
     let base: Vec<_> = PIECES.to_vec();
     let (fields, continuations) = Continuation::new(continuations);
-    let sequence = S::new(preview, base.len());
+    let states = Self { fields, continuations, sequence, hold, first_hold: None, base };
+    states.check_len();
+    states
+  }
+  // adds the states with the empty hold
+  pub fn with_empty_hold(mut self, first_hold: FirstHold) -> Self {
+    assert!(self.hold, "the empty hold needs the hold");
+    self.first_hold = Some(first_hold);
+    self.check_len();
+    self
+  }
+  fn check_len(&self) {
     assert!(
-      (sequence.len() as f64).log2()
-        + (fields.len() as f64).log2()
-        + (if hold { base.len() } else { 1 } as f64).log2()
+      (self.sequence.len() as f64).log2()
+        + (self.fields.len() as f64).log2()
+        + (self.holds() as f64).log2()
         <= (usize::MAX as f64).log2()
     );
-    Self { fields, continuations, sequence, hold, base }
+  }
+  // the number of different holds
+  pub fn holds(&self) -> usize {
+    match self.first_hold {
+      _ if !self.hold => 1,
+      None => self.base.len(),
+      Some(FirstHold::Pull) => 2 * self.base.len() + 1
+    }
+  }
+  fn base_len(&self) -> usize {
+    self.fields.len() * self.holds()
+  }
+  pub fn fields(&self) -> &[Field] {
+    &self.fields
+  }
+  // the placements of the piece on the field, with the indices of the fields after them
+  pub fn field_moves(&self, field: usize, piece: Piece) -> &[(usize, Placement)] {
+    let (left, right) = self.continuations.cont_index[field][piece as usize];
+    &self.continuations.continuations[left..right]
+  }
+  pub fn sequence(&self) -> &S {
+    &self.sequence
+  }
+  pub fn hold(&self) -> bool {
+    self.hold
+  }
+  pub fn first_hold(&self) -> Option<FirstHold> {
+    self.first_hold
+  }
+  // the hold of the states with the empty hold
+  pub fn empty_hold(&self) -> Option<usize> {
+    self.first_hold.map(|_| self.base.len())
+  }
+  // the first of the holds right after pulling, where the pulled piece has to be placed
+  // without holding and the piece in the hold is the one of the hold at this offset
+  pub fn pulled(&self) -> Option<usize> {
+    (self.first_hold == Some(FirstHold::Pull)).then_some(self.base.len() + 1)
+  }
+  // the piece in the hold, `None` for the empty hold or without the hold
+  pub fn held(&self, hold: usize) -> Option<Piece> {
+    let hold = self.pulled().filter(|&pulled| hold >= pulled).map_or(hold, |pulled| hold - pulled);
+    self.base.get(hold).copied().filter(|_| self.hold)
+  }
+  // the hold with the piece, the empty hold for `None`
+  pub fn hold_index(&self, piece: Option<Piece>) -> usize {
+    match piece {
+      _ if !self.hold => 0,
+      Some(piece) => self.base.iter().position(|p| *p == piece).unwrap(),
+      None => self.empty_hold().expect("the states have no empty hold")
+    }
+  }
+  // the mirrored field of each field, kept only if the continuations of both
+  // are mirrored too (which may not hold with asymmetric kicks)
+  fn field_mirrors(&self) -> Vec<Option<usize>> {
+    let field2num = self.fields.iter().enumerate().map(|(i, f)| (*f, i)).collect::<HashMap<_, _>>();
+    let mut mirrors =
+      self.fields.iter().map(|field| field2num.get(&field.mirror()).copied()).collect_vec();
+    let nexts = |field: usize, piece: Piece| {
+      let (left, right) = self.continuations.cont_index[field][piece as usize];
+      self.continuations.continuations[left..right].iter().map(|&(next, _)| next)
+    };
+    loop {
+      let broken = (0..self.fields.len())
+        .filter(|&field| {
+          let Some(mirror) = mirrors[field] else { return false };
+          PIECES.iter().any(|&piece| {
+            let mirrored: Option<Vec<_>> = nexts(field, piece).map(|next| mirrors[next]).collect();
+            mirrored.map_or(true, |mirrored| {
+              mirrored
+                .into_iter()
+                .sorted()
+                .dedup()
+                .ne(nexts(mirror, piece.mirror()).sorted().dedup())
+            })
+          })
+        })
+        .collect_vec();
+      if broken.is_empty() {
+        return mirrors;
+      }
+      for field in broken {
+        if let Some(mirror) = mirrors[field].take() {
+          mirrors[mirror] = None;
+        }
+      }
+    }
+  }
+  // merges every state with its mirror image, where the field is mirrored
+  // and `J`, `L` and `S`, `Z` are swapped in the hold and the sequence,
+  // or `None` if the sequence is not symmetric under the swap
+  pub fn mirror(self) -> Option<MappedStates<Self>> {
+    let fields = self.field_mirrors();
+    let permutation = self
+      .base
+      .iter()
+      .map(|piece| self.base.iter().position(|other| *other == piece.mirror()).unwrap())
+      .collect_vec();
+    let sequence = self.sequence.permute(&permutation)?;
+    let mut mapping = (0..self.len())
+      .into_par_iter()
+      .map(|i| {
+        let (seq, field_hold) = i.div_rem(&self.base_len());
+        let (hold, field) = field_hold.div_rem(&self.fields.len());
+        // the empty hold stays empty, and a pulled piece stays pulled
+        let hold = match self.pulled() {
+          _ if hold < permutation.len() => permutation[hold],
+          Some(pulled) if hold >= pulled => pulled + permutation[hold - pulled],
+          _ => hold
+        };
+        fields[field].map_or(i, |mirror| {
+          i.min(self.base_len() * sequence[seq] + self.fields.len() * hold + mirror)
+        })
+      })
+      .collect::<Vec<_>>();
+    // representatives come before the states mapped to them
+    let mut inverse = Vec::new();
+    for i in 0..mapping.len() {
+      if mapping[i] == i {
+        mapping[i] = inverse.len();
+        inverse.push(i);
+      } else {
+        mapping[i] = mapping[mapping[i]];
+      }
+    }
+    Some(MappedStates { original: self, mapping, inverse })
   }
 }
+
 impl<S: SequenceStates> FieldSequenceStates<S> {
-  fn base_len(&self) -> usize {
-    if self.hold {
-      self.fields.len() * self.base.len()
-    } else {
-      self.fields.len()
+  // a state and its branch placing `current` followed by `queue` for each compatible sequence,
+  // with the probability of the sequence given the queue, where `probabilities` is the
+  // probability of each sequence state before the branch, like the `stationary` ones,
+  // on the field with the hold (see `hold_index`), where the pieces missing at the end of the
+  // preview can be any, the pieces after the preview are drawn after it, and `left` is the
+  // number of each piece left in the bag after the preview if it is known
+  pub fn find_branches(
+    &self,
+    field: &Field,
+    hold: usize,
+    current: Piece,
+    queue: &[Piece],
+    left: Option<&[usize]>,
+    probabilities: &[f64]
+  ) -> Vec<(<Self as States>::State, <Self as States>::Branch, f64)> {
+    let Some(field) = self.fields.iter().position(|f| f == field) else { return vec![] };
+    let queue = queue.iter().map(|&piece| piece as usize).collect_vec();
+    let preview = self.sequence.preview().min(queue.len());
+    let afters = self.sequence.find(&queue[..preview], left).into_iter().collect::<HashSet<_>>();
+    let mut found = vec![];
+    let mut indices = HashMap::new();
+    for (i, &before) in probabilities.iter().enumerate() {
+      let sequence = self.sequence.decode(i).unwrap();
+      let branches = self.sequence.next_pieces(sequence).collect_vec();
+      let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>() as f64;
+      for piece in branches {
+        let after = self.sequence.encode(&piece.gen_state()).unwrap();
+        if piece.gen_piece() != current as usize || !afters.contains(&after) {
+          continue;
+        }
+        let probability = before * piece.gen_weight() as f64 / total;
+        // several states may draw into the same one, which all branch the same
+        let index = *indices.entry(after).or_insert_with(|| {
+          found.push(((field, hold, sequence), (field, hold, piece), 0., after));
+          found.len() - 1
+        });
+        found[index].2 += probability;
+      }
     }
+    if queue.len() > preview {
+      for (_, _, probability, after) in &mut found {
+        *probability *= self.sequence.draw_probability(*after, &queue);
+      }
+    }
+    found.retain(|&(_, _, probability, _)| probability > 0.);
+    let total = found.iter().map(|&(_, _, probability, _)| probability).sum::<f64>();
+    found
+      .into_iter()
+      .map(|(state, branch, probability, _)| (state, branch, probability / total))
+      .collect()
   }
 }
 
@@ -106,6 +428,9 @@ impl SequenceStates for RandomSequenceStates {
   fn new(preview: usize, base_len: usize) -> Self {
     Self { preview, base_len }
   }
+  fn preview(&self) -> usize {
+    self.preview
+  }
   fn encode(&self, state: &Self::State) -> Option<usize> {
     Some(*state)
   }
@@ -119,6 +444,17 @@ impl SequenceStates for RandomSequenceStates {
         state.div_rem(&self.base_len)
       })
   }
+  fn permute(&self, permutation: &[usize]) -> Option<Vec<usize>> {
+    let permuted = (0..self.len())
+      .map(|state| {
+        (0..self.preview).rev().fold(0, |permuted, i| {
+          permuted * self.base_len
+            + permutation[state / self.base_len.pow(i as u32) % self.base_len]
+        })
+      })
+      .collect();
+    Some(permuted)
+  }
 }
 impl HasLength for RandomSequenceStates {
   fn len(&self) -> usize {
@@ -126,18 +462,104 @@ impl HasLength for RandomSequenceStates {
   }
 }
 
-pub type BagSequenceStates = Vec<ArrayVec<(usize, usize), 7>>;
-impl SequenceStates for BagSequenceStates {
+// each piece is rolled up to `rolls` times until it is not one of the last `history` pieces,
+// keeping the last roll anyway, like TGM
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistorySequenceStates {
+  preview: usize,
+  base_len: usize,
+  history: usize,
+  rolls: usize
+}
+impl HistorySequenceStates {
+  pub fn with_history(preview: usize, base_len: usize, history: usize, rolls: usize) -> Self {
+    assert!(rolls > 0, "at least one roll is needed");
+    Self { preview, base_len, history, rolls }
+  }
+  // the number of the last pieces kept in each state, from the oldest at the lowest digit
+  fn kept(&self) -> usize {
+    self.preview.max(self.history)
+  }
+  // the weight of a piece, scaled by `base_len.pow(rolls)`
+  fn piece_weight(&self, in_history: bool, history_len: usize) -> u64 {
+    let (base_len, history_len) = (self.base_len as u64, history_len as u64);
+    if in_history {
+      history_len.pow(self.rolls as u32 - 1)
+    } else {
+      (0..self.rolls as u32)
+        .map(|i| history_len.pow(i) * base_len.pow(self.rolls as u32 - 1 - i))
+        .sum()
+    }
+  }
+}
+impl SequenceStates for HistorySequenceStates {
   type State = usize;
-  type Proxy = (Self::State, usize);
+  type Proxy = (Self::State, usize, u64);
+  // the history of TGM
   fn new(preview: usize, base_len: usize) -> Self {
-    type State = (VecDeque<usize>, Vec<bool>);
-    struct Bfs {
+    Self::with_history(preview, base_len, 4, 4)
+  }
+  fn preview(&self) -> usize {
+    self.preview
+  }
+  fn encode(&self, state: &Self::State) -> Option<usize> {
+    Some(*state)
+  }
+  fn decode(&self, index: usize) -> Option<Self::State> {
+    Some(index)
+  }
+  fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Proxy> {
+    let kept = self.kept();
+    let history = (kept - self.history..kept)
+      .map(|i| state / self.base_len.pow(i as u32) % self.base_len)
+      .collect::<Vec<_>>();
+    let history_len = history.iter().sorted().dedup().count();
+    (0..self.base_len).map(move |piece| {
+      let weight = self.piece_weight(history.contains(&piece), history_len);
+      let state = state + piece * self.base_len.pow(kept as u32);
+      // the piece leaving the preview
+      let current = state / self.base_len.pow((kept - self.preview) as u32) % self.base_len;
+      (state / self.base_len, current, weight)
+    })
+  }
+  fn permute(&self, permutation: &[usize]) -> Option<Vec<usize>> {
+    let permuted = (0..self.len())
+      .map(|state| {
+        (0..self.kept()).rev().fold(0, |permuted, i| {
+          permuted * self.base_len
+            + permutation[state / self.base_len.pow(i as u32) % self.base_len]
+        })
+      })
+      .collect();
+    Some(permuted)
+  }
+}
+impl HasLength for HistorySequenceStates {
+  fn len(&self) -> usize {
+    self.base_len.pow(self.kept() as u32)
+  }
+}
+
+// the queue and the number of each piece left in the current bag of each state
+type BagState = (VecDeque<usize>, Vec<usize>);
+
+// pieces drawn without replacement from a bag, refilled with the same pieces when empty
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BagSequenceStates {
+  nexts: Vec<ArrayVec<(usize, usize, u64), 7>>,
+  states: Vec<BagState>,
+  bag: Vec<usize>
+}
+impl BagSequenceStates {
+  // `bag[i]` is the number of the piece `i` in each bag, like 2 of every piece for the 14-bag
+  pub fn with_bag(preview: usize, bag: &[usize]) -> Self {
+    type State = BagState;
+    struct Bfs<'a> {
       mapping: HashMap<State, usize>,
       inverse: VecDeque<State>,
-      base_len: usize
+      bag: &'a [usize]
     }
-    impl Bfs {
+    impl Bfs<'_> {
       fn find_or_insert(&mut self, state: State) -> usize {
         let len = self.mapping.len();
         *self.mapping.entry(state.clone()).or_insert_with(|| {
@@ -147,38 +569,125 @@ impl SequenceStates for BagSequenceStates {
         })
       }
     }
-    impl Iterator for Bfs {
-      type Item = ArrayVec<(usize, usize), 7>;
+    impl Iterator for Bfs<'_> {
+      type Item = ArrayVec<(usize, usize, u64), 7>;
       fn next(&mut self) -> Option<Self::Item> {
         (!self.inverse.is_empty()).then(move || {
           let mut top = self.inverse.pop_front().unwrap();
-          if top.1.iter().all(|available| !available) {
-            top.1 = (0..self.base_len).map(|_| true).collect();
+          if top.1.iter().all(|&left| left == 0) {
+            top.1 = self.bag.to_vec();
           }
           top
             .1
             .clone()
             .into_iter()
             .enumerate()
-            .filter(|(_, b)| *b)
-            .map(move |(i, _)| {
+            .filter(|&(_, left)| left > 0)
+            .map(move |(i, left)| {
               let mut top = top.clone();
-              top.1[i] = false;
+              top.1[i] -= 1;
               top.0.push_back(i);
               let piece = top.0.pop_front().unwrap();
-              (self.find_or_insert(top), piece)
+              (self.find_or_insert(top), piece, left as u64)
             })
             .collect()
         })
       }
     }
-    let mut bfs = Bfs { mapping: Default::default(), inverse: Default::default(), base_len };
+    let size = bag.iter().sum::<usize>();
+    assert!(size > 0 && bag.len() <= 7, "invalid bag {:?}", bag);
+    let mut bfs = Bfs { mapping: Default::default(), inverse: Default::default(), bag };
     bfs.find_or_insert({
-      let seq: VecDeque<usize> = (0..preview).map(|i| i % base_len).collect();
-      let available = (0..base_len).map(|i| i > seq.back().copied().unwrap_or(base_len)).collect();
-      (seq, available)
+      // the first pieces of the bags in order, with the last bag emptied if it is drawn fully
+      let pieces = bag.iter().enumerate().flat_map(|(i, &count)| std::iter::repeat(i).take(count));
+      let seq: VecDeque<usize> = pieces.clone().cycle().take(preview).collect();
+      let drawn = preview % size;
+      let mut left = if drawn == 0 { vec![0; bag.len()] } else { bag.to_vec() };
+      for piece in pieces.take(drawn) {
+        left[piece] -= 1;
+      }
+      (seq, left)
     });
-    bfs.collect()
+    let nexts: Vec<_> = bfs.by_ref().collect();
+    let mut states = vec![Default::default(); nexts.len()];
+    for (state, i) in bfs.mapping {
+      states[i] = state;
+    }
+    Self { nexts, states, bag: bag.to_vec() }
+  }
+  // the probability of each state at the start of a game, where `drawn` pieces of the first bag
+  // are drawn before the queue, given the first pieces of the queue
+  pub fn starts(&self, drawn: usize, queue: &[usize]) -> Result<Vec<(usize, f64)>, String> {
+    let preview = self.states[0].0.len();
+    if queue.len() > preview {
+      return Err(format!("only {} pieces in the queue", preview));
+    }
+    let mut starts: HashMap<BagState, f64> =
+      HashMap::from([((VecDeque::new(), vec![0; self.bag.len()]), 1.)]);
+    for i in 0..drawn + preview {
+      let mut next_starts = HashMap::new();
+      for ((seq, left), probability) in starts {
+        let left = if left.iter().all(|&left| left == 0) { self.bag.clone() } else { left };
+        let total = left.iter().sum::<usize>() as f64;
+        for piece in (0..left.len()).filter(|&piece| left[piece] > 0) {
+          if i >= drawn && queue.get(i - drawn).is_some_and(|&revealed| revealed != piece) {
+            continue;
+          }
+          let (mut seq, mut left) = (seq.clone(), left.clone());
+          left[piece] -= 1;
+          if i >= drawn {
+            seq.push_back(piece);
+          }
+          let probability = probability * (left[piece] + 1) as f64 / total;
+          *next_starts.entry((seq, left)).or_insert(0.) += probability;
+        }
+      }
+      starts = next_starts;
+    }
+    if starts.is_empty() {
+      let queue = queue.iter().map(|&piece| format!("{:?}", PIECES[piece])).join("");
+      return Err(format!("no bag starts with {} after {} pieces", queue, drawn));
+    }
+    let total = starts.values().sum::<f64>();
+    let indices =
+      self.states.iter().enumerate().map(|(i, state)| (state, i)).collect::<HashMap<_, _>>();
+    let starts = starts.into_iter();
+    Ok(starts.map(|(state, probability)| (indices[&state], probability / total)).collect())
+  }
+  // the pieces of a bag like `IOTSZJLIOTSZJL`, counted in the order of `PIECES`
+  pub fn parse_bag(bag: &str) -> Result<Vec<usize>, String> {
+    let mut counts = vec![0; PIECES.len()];
+    for c in bag.chars().filter(|c| !c.is_whitespace()) {
+      counts[c.to_string().parse::<Piece>()? as usize] += 1;
+    }
+    if counts.iter().all(|&count| count == 0) {
+      return Err("no pieces in the bag".into());
+    }
+    Ok(counts)
+  }
+}
+impl SequenceStates for BagSequenceStates {
+  type State = usize;
+  type Proxy = (Self::State, usize, u64);
+  fn new(preview: usize, base_len: usize) -> Self {
+    Self::with_bag(preview, &vec![1; base_len])
+  }
+  fn preview(&self) -> usize {
+    self.states[0].0.len()
+  }
+  fn bag(&self) -> Option<&[usize]> {
+    Some(&self.bag)
+  }
+  fn find(&self, queue: &[usize], left: Option<&[usize]>) -> Vec<usize> {
+    // a full bag is refilled only when it is drawn from
+    let left = left.map(|left| if left == self.bag { vec![0; left.len()] } else { left.to_vec() });
+    (0..self.states.len())
+      .filter(|&i| {
+        let (seq, state_left) = &self.states[i];
+        seq.iter().take(queue.len()).eq(queue)
+          && left.as_ref().map_or(true, |left| left == state_left)
+      })
+      .collect()
   }
   fn encode(&self, state: &Self::State) -> Option<usize> {
     Some(*state)
@@ -187,11 +696,373 @@ impl SequenceStates for BagSequenceStates {
     Some(index)
   }
   fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Proxy> {
-    self[state].iter().cloned()
+    self.nexts[state].iter().cloned()
+  }
+  fn permute(&self, permutation: &[usize]) -> Option<Vec<usize>> {
+    let indices =
+      self.states.iter().enumerate().map(|(i, state)| (state, i)).collect::<HashMap<_, _>>();
+    self
+      .states
+      .iter()
+      .map(|(seq, left)| {
+        let seq = seq.iter().map(|&piece| permutation[piece]).collect();
+        let mut permuted = vec![0; left.len()];
+        for (piece, &left) in left.iter().enumerate() {
+          permuted[permutation[piece]] = left;
+        }
+        indices.get(&(seq, permuted)).copied()
+      })
+      .collect()
   }
 }
 impl HasLength for BagSequenceStates {
   fn len(&self) -> usize {
-    self.len()
+    self.nexts.len()
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Transition {
+  pub piece: Piece,
+  // the probability relative to the other transitions of the same state
+  pub weight: u64,
+  pub next: String
+}
+
+// a randomizer as named states, each emitting one of its pieces and moving to the next state
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Automaton {
+  pub start: String,
+  pub states: BTreeMap<String, Vec<Transition>>
+}
+
+impl Automaton {
+  pub fn load(path: &std::path::Path) -> Result<Self, String> {
+    Self::parse(&std::fs::read_to_string(path).map_err(|err| err.to_string())?)
+  }
+  pub fn parse(description: &str) -> Result<Self, String> {
+    let automaton: Self = serde_json::from_str(description).map_err(|err| err.to_string())?;
+    if !automaton.states.contains_key(&automaton.start) {
+      return Err(format!("unknown start {:?}", automaton.start));
+    }
+    for (name, transitions) in automaton.states.iter() {
+      if transitions.is_empty() || transitions.len() > 7 {
+        return Err(format!("{:?} must have 1 to 7 transitions", name));
+      }
+      for transition in transitions {
+        if transition.weight == 0 {
+          return Err(format!("{:?} has a transition with no weight", name));
+        }
+        if !automaton.states.contains_key(&transition.next) {
+          return Err(format!("unknown state {:?}", transition.next));
+        }
+      }
+    }
+    Ok(automaton)
+  }
+}
+
+// the automaton state and the queue of each state
+type AutomatonState = (usize, VecDeque<usize>);
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct AutomatonSequenceStates {
+  nexts: Vec<ArrayVec<(usize, usize, u64), 7>>,
+  states: Vec<AutomatonState>
+}
+impl AutomatonSequenceStates {
+  // the pieces are in the order of `PIECES`
+  pub fn with_automaton(preview: usize, automaton: &Automaton) -> Self {
+    let names = automaton.states.keys().collect_vec();
+    let index = |name: &String| names.binary_search(&name).unwrap();
+    let transitions = automaton
+      .states
+      .values()
+      .map(|transitions| {
+        transitions.iter().map(|t| (t.piece as usize, t.weight, index(&t.next))).collect_vec()
+      })
+      .collect_vec();
+    // the queues are filled from the start before the first piece
+    let mut seeds = vec![(index(&automaton.start), VecDeque::new())];
+    for _ in 0..preview {
+      seeds = seeds
+        .into_iter()
+        .flat_map(|(state, queue)| {
+          transitions[state].iter().map(move |&(piece, _, next)| {
+            let mut queue = queue.clone();
+            queue.push_back(piece);
+            (next, queue)
+          })
+        })
+        .sorted()
+        .dedup()
+        .collect();
+    }
+    let mut mapping: HashMap<AutomatonState, usize> = HashMap::new();
+    let mut states = vec![];
+    let mut find_or_insert = |state: AutomatonState, states: &mut Vec<AutomatonState>| {
+      *mapping.entry(state.clone()).or_insert_with(|| {
+        states.push(state);
+        states.len() - 1
+      })
+    };
+    for seed in seeds {
+      find_or_insert(seed, &mut states);
+    }
+    let mut nexts = vec![];
+    while nexts.len() < states.len() {
+      let (state, queue) = states[nexts.len()].clone();
+      let next = transitions[state]
+        .iter()
+        .map(|&(piece, weight, next)| {
+          let mut queue = queue.clone();
+          queue.push_back(piece);
+          let current = queue.pop_front().unwrap();
+          (find_or_insert((next, queue), &mut states), current, weight)
+        })
+        .collect();
+      nexts.push(next);
+    }
+    Self { nexts, states }
+  }
+}
+impl SequenceStates for AutomatonSequenceStates {
+  type State = usize;
+  type Proxy = (Self::State, usize, u64);
+  // a single state emitting every piece equally
+  fn new(preview: usize, base_len: usize) -> Self {
+    let transitions = PIECES[..base_len]
+      .iter()
+      .map(|&piece| Transition { piece, weight: 1, next: String::new() })
+      .collect();
+    let automaton =
+      Automaton { start: String::new(), states: BTreeMap::from([(String::new(), transitions)]) };
+    Self::with_automaton(preview, &automaton)
+  }
+  fn preview(&self) -> usize {
+    self.states[0].1.len()
+  }
+  fn encode(&self, state: &Self::State) -> Option<usize> {
+    Some(*state)
+  }
+  fn decode(&self, index: usize) -> Option<Self::State> {
+    Some(index)
+  }
+  fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Proxy> {
+    self.nexts[state].iter().cloned()
+  }
+  // the states are matched by the pieces they go on to draw, refining a partition of the states
+  // and their permuted copies `len..2 * len` until it is stable,
+  // as different automaton states may draw the same pieces
+  fn permute(&self, permutation: &[usize]) -> Option<Vec<usize>> {
+    let len = self.len();
+    let nexts = |i: usize| {
+      let (state, permuted) = (i % len, i >= len);
+      let gcd = self.nexts[state].iter().fold(0, |a, (_, _, weight)| a.gcd(weight));
+      self.nexts[state].iter().map(move |&(next, piece, weight)| {
+        if permuted {
+          (next + len, permutation[piece], weight / gcd)
+        } else {
+          (next, piece, weight / gcd)
+        }
+      })
+    };
+    let mut classes = vec![0; 2 * len];
+    let mut count = 1;
+    loop {
+      let mut ids = HashMap::new();
+      let refined = (0..2 * len)
+        .map(|i| {
+          let key = nexts(i).map(|(next, piece, weight)| (piece, weight, classes[next])).sorted();
+          let id = ids.len();
+          *ids.entry((classes[i], key.collect_vec())).or_insert(id)
+        })
+        .collect_vec();
+      classes = refined;
+      if ids.len() == count {
+        break;
+      }
+      count = ids.len();
+    }
+    let mut representatives = HashMap::new();
+    for (i, &class) in classes[..len].iter().enumerate() {
+      representatives.entry(class).or_insert(i);
+    }
+    (len..2 * len).map(|i| representatives.get(&classes[i]).copied()).collect()
+  }
+}
+impl HasLength for AutomatonSequenceStates {
+  fn len(&self) -> usize {
+    self.nexts.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::continuations::from_text;
+  use crate::evaluator::{Evaluator, ValueIterator};
+
+  // `XXX.` and `.XXX` are mirrors of each other, and `X..X/X..X` of itself
+  const SYMMETRIC: &str = "
+field XXX.
+I X..X/X..X@0,0,0,hard,1
+J .XXX@0,0,0,hard,1
+O
+T
+S
+Z
+L
+
+field .XXX
+I X..X/X..X@0,0,0,hard,1
+L XXX.@0,0,0,hard,1
+O
+T
+S
+Z
+J
+
+field X..X/X..X
+O XXX.@0,0,0,hard,1 .XXX@0,0,0,hard,1
+S XXX.@0,0,0,hard,1
+Z .XXX@0,0,0,hard,1
+I
+T
+J
+L
+";
+
+  fn values<T: States>(states: &T) -> Vec<f64> {
+    let mut evaluator = ValueIterator::new(states);
+    for _ in 0..20 {
+      evaluator.next();
+    }
+    evaluator.values
+  }
+
+  // the probability of drawing each piece into the end of the preview from the bag state
+  fn bag_draws(sequence: &BagSequenceStates, state: usize) -> Vec<f64> {
+    let branches = sequence.next_pieces(state).collect_vec();
+    let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>() as f64;
+    let mut probabilities = vec![0.; PIECES.len()];
+    for (after, current, weight) in branches {
+      assert_eq!(current, sequence.states[state].0[0]);
+      probabilities[*sequence.states[after].0.back().unwrap()] += weight as f64 / total;
+    }
+    probabilities
+  }
+
+  #[test]
+  fn bag_draw_probabilities() {
+    let sequence = BagSequenceStates::with_bag(1, &[2; 7]);
+    // I is in the preview, so one I and two of the other pieces are left
+    assert_eq!(sequence.states[0], (VecDeque::from([0]), vec![1, 2, 2, 2, 2, 2, 2]));
+    assert_eq!(bag_draws(&sequence, 0), [1., 2., 2., 2., 2., 2., 2.].map(|n| n / 13.));
+    let refill = sequence.states.iter().position(|(_, left)| left.iter().all(|&n| n == 0));
+    assert_eq!(bag_draws(&sequence, refill.unwrap()), [1. / 7.; 7]);
+    let last = sequence.states.iter().position(|(_, left)| left == &[0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(bag_draws(&sequence, last.unwrap()), [0., 0., 0., 0., 0., 0., 1.]);
+  }
+
+  // the probability of drawing each piece after the history of the last 4 pieces, oldest first,
+  // with 4 rolls and 1 preview
+  fn history_draws(history: [Piece; 4]) -> Vec<f64> {
+    let sequence = HistorySequenceStates::with_history(1, PIECES.len(), 4, 4);
+    let state = history.iter().rev().fold(0, |state, &piece| state * PIECES.len() + piece as usize);
+    let branches = sequence.next_pieces(state).collect_vec();
+    let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>() as f64;
+    let mut probabilities = vec![0.; PIECES.len()];
+    for (after, current, weight) in branches {
+      // the piece in the preview is placed
+      assert_eq!(current, history[3] as usize);
+      probabilities[after / PIECES.len().pow(3)] += weight as f64 / total;
+    }
+    probabilities
+  }
+
+  #[test]
+  fn history_reroll_probabilities() {
+    use Piece::*;
+    // a piece of the history is drawn only if every roll is in the history and the last is it
+    let probabilities = history_draws([Z, Z, Z, Z]);
+    assert!((probabilities[Z as usize] - 1. / 2401.).abs() < 1e-12);
+    assert!((probabilities[I as usize] - 400. / 2401.).abs() < 1e-12);
+    let probabilities = history_draws([S, Z, S, Z]);
+    assert!((probabilities[S as usize] - 8. / 2401.).abs() < 1e-12);
+    assert!((probabilities[T as usize] - 477. / 2401.).abs() < 1e-12);
+    assert!((probabilities.iter().sum::<f64>() - 1.).abs() < 1e-12);
+  }
+
+  #[test]
+  fn nes_probabilities() {
+    let automaton = Automaton::parse(include_str!("../../sequences/nes.json")).unwrap();
+    let sequence = AutomatonSequenceStates::with_automaton(1, &automaton);
+    for state in 0..sequence.len() {
+      let branches = sequence.next_pieces(state).collect_vec();
+      let total = branches.iter().map(|branch| branch.gen_weight()).sum::<u64>();
+      assert_eq!(total, 56);
+      let last = *sequence.states[state].1.back().unwrap();
+      for (after, _, weight) in branches {
+        let drawn = *sequence.states[after].1.back().unwrap();
+        assert_eq!(weight, if drawn == last { 2 } else { 9 });
+      }
+    }
+    assert!(Automaton::parse(r#"{"start": "A", "states": {}}"#).is_err());
+  }
+
+  #[test]
+  fn one_hold_per_piece() {
+    let continuations = from_text(SYMMETRIC).unwrap();
+    let states =
+      FieldSequenceStates::with_sequence(&continuations, true, BagSequenceStates::new(1, 7))
+        .with_empty_hold(FirstHold::Pull);
+    let empty = states.hold_index(None);
+    for sequence in 0..states.sequence().len() {
+      for field in 0..states.fields().len() {
+        for branch in states.next_pieces((field, empty, sequence)) {
+          let held = states.next_moves(branch).filter(|(_, _, placement)| placement.is_none());
+          let (pulled, piece, _) = held.exactly_one().ok().unwrap();
+          assert_eq!(states.held(pulled.1), Some(piece));
+          // the pulled piece is placed, and the held one stays in the hold
+          for branch in states.next_pieces(pulled) {
+            let current = PIECES[branch.2.gen_piece()];
+            for ((_, hold, _), placed, placement) in states.next_moves(branch) {
+              assert_eq!((placed, hold, placement.is_some()), (current, piece as usize, true));
+            }
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn bag_starts() {
+    let sequence = BagSequenceStates::with_bag(2, &[1; 7]);
+    let starts = sequence.starts(0, &[]).unwrap();
+    assert_eq!(starts.len(), 42);
+    assert!(starts.iter().all(|&(_, probability)| (probability - 1. / 42.).abs() < 1e-12));
+    // after 6 pieces, the last piece of the first bag is followed by any of the next bag
+    let starts = sequence.starts(6, &[Piece::T as usize]).unwrap();
+    assert_eq!(starts.len(), 7);
+    assert!(sequence.starts(0, &[0, 1, 2]).is_err());
+    assert!(sequence.starts(0, &[0, 0]).is_err());
+  }
+
+  #[test]
+  fn mirror_preserves_values() {
+    let continuations = from_text(SYMMETRIC).unwrap();
+    let states = || {
+      FieldSequenceStates::with_sequence(&continuations, true, BagSequenceStates::new(1, 7))
+        .with_empty_hold(FirstHold::Pull)
+    };
+    let original = values(&states());
+    let mirrored = states().mirror().unwrap();
+    assert!(mirrored.len() < original.len());
+    let merged = values(&mirrored);
+    assert!(original.iter().any(|&value| value > 1.));
+    for (state, &value) in original.iter().enumerate() {
+      assert!((value - merged[mirrored.mapping[state]]).abs() < 1e-9);
+    }
   }
 }
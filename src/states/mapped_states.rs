@@ -16,7 +16,7 @@ impl<T: States> HasLength for MappedStates<T> {
 
 impl<T: States> States for MappedStates<T> {
   type State = usize;
-  type Branch = Vec<usize>;
+  type Branch = (Vec<usize>, u64);
   fn decode(&self, index: usize) -> Option<Self::State> {
     Some(index)
   }
@@ -26,21 +26,26 @@ impl<T: States> States for MappedStates<T> {
   fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Branch> {
     self.original.get_next(self.inverse[state], &*self.mapping).into_iter()
   }
-  fn next_states(&self, piece: Self::Branch) -> impl Iterator<Item=Self::State> {
+  fn next_states(&self, (piece, _): Self::Branch) -> impl Iterator<Item=Self::State> {
     piece.into_iter()
   }
+  fn weight(&self, (_, weight): &Self::Branch) -> u64 {
+    *weight
+  }
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct ConcreteMappedStates<T: States> {
   pub original: T,
   pub mapping: Vec<usize>,
-  pub nexts: Continuation
+  pub nexts: Continuation,
+  // the weight of each branch in `nexts`
+  pub weights: Vec<ArrayVec<u64, 7>>
 }
 
 impl<T: States> States for ConcreteMappedStates<T> {
   type State = usize;
-  type Branch = (usize, usize);
+  type Branch = (usize, usize, u64);
   fn encode(&self, state: &Self::State) -> Option<usize> {
     Some(*state)
   }
@@ -48,11 +53,17 @@ impl<T: States> States for ConcreteMappedStates<T> {
     Some(index)
   }
   fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Branch> {
-    self.nexts.cont_index[state].iter().cloned()
+    self.nexts.cont_index[state]
+      .iter()
+      .zip(self.weights[state].iter())
+      .map(|(&(left, right), &weight)| (left, right, weight))
   }
-  fn next_states(&self, (left, right): Self::Branch) -> impl Iterator<Item=Self::State> {
+  fn next_states(&self, (left, right, _): Self::Branch) -> impl Iterator<Item=Self::State> {
     self.nexts.continuations[left..right].iter().cloned()
   }
+  fn weight(&self, &(_, _, weight): &Self::Branch) -> u64 {
+    weight
+  }
 }
 
 impl<T: States> HasLength for ConcreteMappedStates<T> {
@@ -70,11 +81,28 @@ impl<T: States> MappedStates<MappedStates<T>> {
   }
 }
 
+// the branches of the states as a `Continuation` and their weights
+fn concrete_nexts<T: States>(
+  states: &T,
+  mapping: &[usize],
+  inverse: Vec<usize>
+) -> (Continuation, Vec<ArrayVec<u64, 7>>) {
+  let mut weights = Vec::with_capacity(inverse.len());
+  let nexts = inverse
+    .into_iter()
+    .map(|i| {
+      let next = states.get_next(i, mapping);
+      weights.push(next.iter().map(|&(_, weight)| weight).collect());
+      next.into_iter().map(|(next, _)| next)
+    })
+    .collect();
+  (nexts, weights)
+}
+
 impl<T: States> MappedStates<T> {
   pub fn concrete(self) -> ConcreteMappedStates<T> {
-    let nexts =
-      self.inverse.into_iter().map(|i| self.original.get_next(i, &*self.mapping)).collect();
-    ConcreteMappedStates { original: self.original, mapping: self.mapping, nexts }
+    let (nexts, weights) = concrete_nexts(&self.original, &self.mapping, self.inverse);
+    ConcreteMappedStates { original: self.original, mapping: self.mapping, nexts, weights }
   }
 }
 
@@ -82,8 +110,8 @@ impl<T: States> MappedStates<ConcreteMappedStates<T>> {
   // TODO: sort by self.inverse first to remap current mapping
   // so that self.original.nexts can be incrementally changed without extra memory overhead
   pub fn compose(mut self) -> ConcreteMappedStates<T> {
-    self.original.nexts =
-      self.inverse.into_iter().map(|i| self.original.get_next(i, &*self.mapping)).collect();
+    (self.original.nexts, self.original.weights) =
+      concrete_nexts(&self.original, &self.mapping, self.inverse);
     self.original.mapping.par_iter_mut().for_each(|i| *i = self.mapping[*i]);
     self.original
   }
@@ -95,7 +123,8 @@ impl<T: States> ConcreteMappedStates<MappedStates<T>> {
     ConcreteMappedStates {
       original: self.original.original,
       mapping: self.original.mapping,
-      nexts: self.nexts
+      nexts: self.nexts,
+      weights: self.weights
     }
   }
 }
@@ -106,7 +135,8 @@ impl<T: States> ConcreteMappedStates<ConcreteMappedStates<T>> {
     ConcreteMappedStates {
       original: self.original.original,
       mapping: self.original.mapping,
-      nexts: self.nexts
+      nexts: self.nexts,
+      weights: self.weights
     }
   }
 }
\ No newline at end of file
@@ -4,10 +4,11 @@ mod field_sequence_states;
 pub use field_sequence_states::*;
 
 use crate::basics::{Field, Piece, PIECES};
+use crate::continuations::{Continuations, Placement};
 use arrayvec::ArrayVec;
 use itertools::Itertools;
 use num_integer::Integer;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /* pub trait PrintableStateProxy: StateProxy {
   type MarkovState: std::fmt::Display+Ord+PartialEq+Clone+Send;
@@ -15,11 +16,7 @@ use std::collections::{HashMap, VecDeque};
 } */
 
 pub trait Creatable<'a> {
-  fn new(
-    continuations: &'a HashMap<Field, HashMap<Piece, Vec<Field>>>,
-    preview: usize,
-    hold: bool
-  ) -> Self;
+  fn new(continuations: &'a Continuations, preview: usize, hold: bool) -> Self;
 }
 
 pub trait HasLength {
@@ -36,6 +33,24 @@ pub trait States: HasLength+std::marker::Sync {
   fn encode(&self, state: &Self::State) -> Option<usize>;
   fn next_pieces(&self, state: Self::State) -> impl Iterator<Item=Self::Branch>;
   fn next_states(&self, piece: Self::Branch) -> impl Iterator<Item=Self::State>;
+  // the probability of the branch relative to the other branches of its state
+  fn weight(&self, _piece: &Self::Branch) -> u64 {
+    1
+  }
+  // the evaluators count every move as a combo, so the counts from the state are this many more
+  // than its combos when some of the moves after it place no piece, the same whichever way it goes
+  fn extra_moves(&self, _state: &Self::State) -> usize {
+    0
+  }
+}
+
+// states whose transitions place pieces, for objectives that depend on the placements
+pub trait MoveStates: States {
+  // `next_states` with the piece and its placement, `None` if the move places no piece
+  fn next_moves(
+    &self,
+    piece: Self::Branch
+  ) -> impl Iterator<Item=(Self::State, Piece, Option<Placement>)>;
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -44,8 +59,8 @@ pub struct Continuation<T=usize> {
   pub continuations: Vec<T>
 }
 
-impl Continuation<usize> {
-  fn new(continuations: &HashMap<Field, HashMap<Piece, Vec<Field>>>) -> (Vec<Field>, Self) {
+impl Continuation<(usize, Placement)> {
+  fn new(continuations: &Continuations) -> (Vec<Field>, Self) {
     let fields = continuations.keys().cloned().collect::<Vec<Field>>();
     let field2num = fields.iter().enumerate().map(|(i, f)| (*f, i)).collect::<HashMap<_, _>>();
     let mut cont_index: Vec<ArrayVec<(usize, usize), 7>> = Vec::new();
@@ -56,8 +71,8 @@ impl Continuation<usize> {
           .map(|i| {
             let begin = cont.len();
             let piece = Piece::num2piece(i);
-            for &next_field in &continuations[&field][&piece] {
-              cont.push(field2num[&next_field]);
+            for &(next_field, placement) in &continuations[&field][&piece] {
+              cont.push((field2num[&next_field], placement));
             }
             (begin, cont.len())
           })
@@ -132,12 +147,12 @@ pub trait GetNext {
     &self,
     i: usize,
     maximal_func: F
-  ) -> ArrayVec<I, 7>;
+  ) -> ArrayVec<(I, u64), 7>;
   fn get_next<'a, U: Into<Option<&'a [usize]>>+Copy>(
     &self,
     i: usize,
     res: U
-  ) -> ArrayVec<Vec<usize>, 7> {
+  ) -> ArrayVec<(Vec<usize>, u64), 7> {
     if let Some(res) = res.into() {
       self.true_get_next(i, |v| {
         let mut v2 = v.into_iter().map(|i| res[i]).collect::<Vec<_>>();
@@ -158,10 +173,11 @@ pub trait GetNext {
   }
 }
 
-fn next2id(nexts: ArrayVec<Vec<usize>, 7>) -> Vec<usize> {
+fn next2id(nexts: ArrayVec<(Vec<usize>, u64), 7>) -> Vec<usize> {
   std::iter::once(nexts.len())
-    .chain(nexts.iter().map(|v| v.len()))
-    .chain(nexts.iter().flatten().cloned())
+    .chain(nexts.iter().map(|(_, weight)| *weight as usize))
+    .chain(nexts.iter().map(|(v, _)| v.len()))
+    .chain(nexts.iter().flat_map(|(v, _)| v.iter().cloned()))
     .collect()
 }
 
@@ -170,68 +186,28 @@ impl<T: States> GetNext for T {
     &self,
     i: usize,
     maximal_func: F
-  ) -> ArrayVec<I, 7> {
+  ) -> ArrayVec<(I, u64), 7> {
     let state = self.decode(i).unwrap();
     let mut nexts: ArrayVec<_, 7> = self
       .next_pieces(state)
       .map(|piece| {
+        let weight = self.weight(&piece);
         let next =
           self.next_states(piece).map(|state| self.encode(&state).unwrap()).collect_vec();
-        maximal_func(next)
+        (maximal_func(next), weight)
       })
       .collect();
     nexts.sort_unstable();
-    let gcd = nexts.iter().count_same().fold(0, |a, (_, b)| a.gcd(&b));
-    if gcd > 1 {
-      nexts.into_iter().step_by(gcd).collect()
-    } else {
-      nexts
-    }
-  }
-}
-
-struct CountSame<Item: PartialEq, I: IntoIterator<Item=Item>> {
-  iter: I::IntoIter,
-  last: Option<I::Item>,
-  count: usize
-}
-
-impl<Item: PartialEq, I: IntoIterator<Item=Item>> Iterator for CountSame<Item, I> {
-  type Item = (I::Item, usize);
-  fn next(&mut self) -> Option<Self::Item> {
-    for item in self.iter.by_ref() {
-      if self.last.is_none() {
-        self.last = Some(item);
-        self.count = 1;
-      } else {
-        let last = self.last.take().unwrap();
-        if last == item {
-          self.last = Some(item);
-          self.count += 1;
-        } else {
-          self.last = Some(item);
-          let count = self.count;
-          self.count = 1;
-          return Some((last, count));
-        }
+    // equal branches are merged, with the weights reduced to the smallest integers
+    let mut merged: ArrayVec<(I, u64), 7> = ArrayVec::new();
+    for (next, weight) in nexts {
+      match merged.last_mut() {
+        Some((last, last_weight)) if *last == next => *last_weight += weight,
+        _ => merged.push((next, weight))
       }
     }
-    if self.last.is_some() {
-      let last = self.last.take().unwrap();
-      self.last = None;
-      Some((last, self.count))
-    } else {
-      None
-    }
-  }
-}
-
-trait CountSameExt<Item: PartialEq, I: IntoIterator<Item=Item>> {
-  fn count_same(self) -> CountSame<Item, I>;
-}
-
-impl<Item: PartialEq, I: IntoIterator<Item=Item>> CountSameExt<Item, I> for I {
-  fn count_same(self) -> CountSame<Item, I> {
-    CountSame { iter: self.into_iter(), last: None, count: 0 }
+    let gcd = merged.iter().fold(0, |a, (_, b)| a.gcd(b));
+    merged.iter_mut().for_each(|(_, weight)| *weight /= gcd);
+    merged
   }
 }
@@ -0,0 +1,207 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+// a cycle of 4-wide fields in the well at column 3 with SRS placements, where `X.../XX..` has no
+// placements
+const CONTINUATIONS: &str = "
+field XXX.
+I ...X/...X/...X@1,1,0,hard,1
+O
+T
+S
+Z
+J
+L
+
+field ...X/...X/...X
+I
+O
+T
+S
+Z
+J ...X/X..X@0,0,-1,hard,1
+L
+
+field ...X/X..X
+I
+O .XXX@0,1,0,hard,1
+T
+S
+Z
+J
+L
+
+field .XXX
+I X.../X.../X...@1,-2,0,hard,1
+O
+T X.../XX..@1,-1,0,hard,1
+S
+Z
+J
+L
+
+field X.../X.../X...
+I
+O
+T
+S
+Z
+J
+L X.../X..X@0,1,-1,hard,1
+
+field X.../XX..
+I
+O
+T
+S
+Z
+J
+L
+
+field X.../X..X
+I
+O XXX.@0,1,0,hard,1
+T
+S
+Z
+J
+L
+";
+
+const WELL: usize = 3;
+
+// the board with the field in the well and garbage outside it up to the top of the field
+fn board(field: &str) -> Value {
+  let rows = field.split('/').rev().collect::<Vec<_>>();
+  let board = (0..40)
+    .map(|y| {
+      (0..10)
+        .map(|x| {
+          let filled = match rows.get(y) {
+            Some(row) if (WELL..WELL + row.len()).contains(&x) => {
+              row.as_bytes()[x - WELL] == b'X'
+            }
+            Some(_) => true,
+            None => false
+          };
+          filled.then_some("G")
+        })
+        .collect::<Vec<_>>()
+    })
+    .collect::<Vec<_>>();
+  json!(board)
+}
+
+fn location(piece: &str, orientation: &str, x: i32, y: i32) -> Value {
+  json!({ "type": piece, "orientation": orientation, "x": x, "y": y })
+}
+
+struct Session {
+  bot: Child,
+  stdout: BufReader<ChildStdout>
+}
+
+impl Session {
+  fn send(&mut self, message: Value) {
+    writeln!(self.bot.stdin.as_mut().unwrap(), "{}", message).unwrap();
+  }
+  fn receive(&mut self) -> Value {
+    let mut line = String::new();
+    self.stdout.read_line(&mut line).unwrap();
+    serde_json::from_str(&line).unwrap()
+  }
+  // plays the only suggested move at the location
+  fn play(&mut self, location: Value) {
+    self.send(json!({ "type": "suggest" }));
+    let suggestion = self.receive();
+    assert_eq!(suggestion["type"], "suggestion");
+    let moves = suggestion["moves"].as_array().unwrap();
+    assert_eq!(moves.iter().map(|mv| &mv["location"]).collect::<Vec<_>>(), [&location]);
+    self.send(json!({ "type": "play", "move": moves[0] }));
+  }
+  fn new_piece(&mut self, piece: &str) {
+    self.send(json!({ "type": "new_piece", "piece": piece }));
+  }
+}
+
+#[test]
+fn scripted_session() {
+  let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("tbp_session");
+  std::fs::create_dir_all(&dir).unwrap();
+  let continuation = dir.join("continuation.txt");
+  let policy = dir.join("policy.bin");
+  std::fs::write(&continuation, CONTINUATIONS).unwrap();
+  let status = Command::new(env!("CARGO_BIN_EXE_c4w"))
+    .args(["--preview", "1", "--hold", "--first-hold", "pull", "--continuation"])
+    .arg(&continuation)
+    .arg("--policy")
+    .arg(&policy)
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .unwrap();
+  assert!(status.success());
+
+  let mut bot = Command::new(env!("CARGO_BIN_EXE_c4w_tbp"))
+    .arg("--policy")
+    .arg(&policy)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  let stdout = BufReader::new(bot.stdout.take().unwrap());
+  let mut session = Session { bot, stdout };
+  assert_eq!(session.receive()["type"], "info");
+  session.send(json!({ "type": "rules" }));
+  assert_eq!(session.receive()["type"], "ready");
+
+  let start = |field: &str, bag_state: &[&str]| {
+    json!({
+      "type": "start",
+      "hold": null,
+      "queue": ["T", "I", "J", "O", "L", "S"],
+      "combo": 0,
+      "back_to_back": false,
+      "board": board(field),
+      "randomizer": { "type": "seven_bag", "bag_state": bag_state }
+    })
+  };
+  session.send(start("X...", &["Z"]));
+  session.send(json!({ "type": "suggest" }));
+  assert_eq!(session.receive()["moves"], json!([]));
+  // O is drawn twice from the same bag, so the bag is dropped but the game goes on
+  session.send(start("XXX.", &["O"]));
+  session.play(location("I", "east", 6, 2));
+
+  // the first bag is TIJOLS and Z, the second IOJT so far
+  session.send(start("XXX.", &["Z"]));
+  // T is held into the empty hold, pulling I
+  session.play(location("I", "east", 6, 2));
+  session.new_piece("Z");
+  session.new_piece("I");
+  session.play(location("J", "north", 4, 0));
+  session.new_piece("O");
+  session.play(location("O", "north", 4, 0));
+  session.new_piece("J");
+  // L has no placements, so it is swapped with the held T
+  session.play(location("T", "east", 3, 1));
+  session.new_piece("T");
+  session.send(json!({ "type": "suggest" }));
+  assert_eq!(session.receive()["moves"], json!([]));
+  session.new_piece("T");
+  session.send(json!({ "type": "quit" }));
+
+  drop(session.bot.stdin.take());
+  let output = session.bot.wait_with_output().unwrap();
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap().lines().collect::<Vec<_>>(),
+    [
+      "the board is not a field of the continuations",
+      "the bag state does not match the queue",
+      "T is not left in the bag"
+    ]
+  );
+}